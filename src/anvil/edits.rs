@@ -0,0 +1,134 @@
+use std::path::{Component, Path};
+
+use crate::error::SlagError;
+
+/// Opens a file block: `=== FILE: relative/path ===`
+const OPEN: &str = "=== FILE:";
+const OPEN_END: &str = "===";
+/// Closes a file block
+const CLOSE: &str = "=== END FILE ===";
+
+/// New contents for one file, sent by a smith that cannot edit files itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileBlock {
+    pub path: String,
+    pub content: String,
+}
+
+/// Pull every file block out of a smith response.
+/// An unterminated block means the response was cut off and is an error.
+pub fn parse(response: &str) -> Result<Vec<FileBlock>, SlagError> {
+    let mut blocks = Vec::new();
+    let mut lines = response.lines();
+    while let Some(line) = lines.next() {
+        let Some(path) = open_path(line) else {
+            continue;
+        };
+        let mut content = String::new();
+        let mut closed = false;
+        for body in lines.by_ref() {
+            if body.trim_end() == CLOSE {
+                closed = true;
+                break;
+            }
+            content.push_str(body);
+            content.push('\n');
+        }
+        if !closed {
+            return Err(SlagError::BadFileBlock(format!(
+                "{path}: missing \"{CLOSE}\""
+            )));
+        }
+        blocks.push(FileBlock { path, content });
+    }
+    Ok(blocks)
+}
+
+fn open_path(line: &str) -> Option<String> {
+    let path = line
+        .trim_end()
+        .strip_prefix(OPEN)?
+        .strip_suffix(OPEN_END)?
+        .trim();
+    Some(path.to_string())
+}
+
+/// Write the blocks under `root`. Every path is checked before anything is
+/// written, so a bad block leaves the tree untouched. Returns the paths written.
+pub fn apply(blocks: &[FileBlock], root: &Path) -> Result<Vec<String>, SlagError> {
+    for block in blocks {
+        check_path(&block.path)?;
+    }
+    for block in blocks {
+        let target = root.join(&block.path);
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&target, &block.content)?;
+    }
+    Ok(blocks.iter().map(|b| b.path.clone()).collect())
+}
+
+/// Relative, no `..`, and never inside `.git`
+fn check_path(path: &str) -> Result<(), SlagError> {
+    let p = Path::new(path);
+    let normal = !path.is_empty() && p.components().all(|c| matches!(c, Component::Normal(_)));
+    let in_git = p.components().next() == Some(Component::Normal(".git".as_ref()));
+    if !normal || in_git {
+        return Err(SlagError::BadFileBlock(format!(
+            "{path}: must be a relative path inside the project"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_blocks_around_prose() {
+        let response = "Here you go.\n\
+            === FILE: src/lib.rs ===\n\
+            pub fn one() -> u8 {\n    1\n}\n\
+            === END FILE ===\n\
+            === FILE: README.md ===\n\
+            === END FILE ===\n\
+            CMD: cargo test\n";
+        let blocks = parse(response).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].path, "src/lib.rs");
+        assert_eq!(blocks[0].content, "pub fn one() -> u8 {\n    1\n}\n");
+        assert_eq!(blocks[1].content, "");
+        assert!(parse("CMD: true").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unterminated_block_is_an_error() {
+        assert!(parse("=== FILE: a.rs ===\nfn a() {}\n").is_err());
+    }
+
+    #[test]
+    fn applies_inside_root_only() {
+        let root = tempfile::tempdir().unwrap();
+        let block = |path: &str| FileBlock {
+            path: path.into(),
+            content: "x\n".into(),
+        };
+
+        let written = apply(&[block("a/b/c.txt")], root.path()).unwrap();
+        assert_eq!(written, ["a/b/c.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(root.path().join("a/b/c.txt")).unwrap(),
+            "x\n"
+        );
+
+        for bad in ["../escape.txt", "/etc/passwd", ".git/config", ""] {
+            assert!(
+                apply(&[block("ok.txt"), block(bad)], root.path()).is_err(),
+                "{bad}"
+            );
+        }
+        assert!(!root.path().join("ok.txt").exists());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod edits;
 pub mod worktree;
 
-// The parallel anvil logic is integrated directly into pipeline/forge.rs
-// using tokio::task::JoinSet. This module exists for the worktree feature
-// and any future anvil-specific coordination logic.
+// The parallel anvil pool is integrated directly into pipeline/forge.rs
+// as a streaming tokio::task::JoinSet owned by the forge coordinator.
+// This module exists for the worktree feature and any future
+// anvil-specific coordination logic.
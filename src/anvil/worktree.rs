@@ -55,6 +55,118 @@ pub async fn merge_and_cleanup(ingot_id: &str) -> Result<(), SlagError> {
     Ok(())
 }
 
+/// Hand a fanout candidate's worktree to its ingot: branch `forge/{slot}`
+/// becomes `forge/{ingot_id}` and the directory moves to match. A stale
+/// worktree or branch of the ingot, left by an interrupted run, is
+/// discarded first. Returns the new worktree path.
+pub async fn adopt(slot: &str, ingot_id: &str) -> Result<String, SlagError> {
+    discard(ingot_id).await;
+    let dir = format!("../slag-anvil-{ingot_id}");
+    let steps: [&[&str]; 2] = [
+        &["worktree", "move", &format!("../slag-anvil-{slot}"), &dir],
+        &[
+            "branch",
+            "-M",
+            &format!("forge/{slot}"),
+            &format!("forge/{ingot_id}"),
+        ],
+    ];
+    for args in steps {
+        let output = tokio::process::Command::new("git")
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| SlagError::WorktreeError(format!("spawn failed: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(SlagError::WorktreeError(format!(
+                "git {} failed: {stderr}",
+                args[..2].join(" ")
+            )));
+        }
+    }
+    Ok(dir)
+}
+
+/// Fanout candidate slots (`{ingot_id}.N`) that still have a worktree or branch
+pub async fn candidate_slots(ingot_id: &str) -> Vec<String> {
+    let git = |args: Vec<String>| async move {
+        tokio::process::Command::new("git")
+            .args(args)
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default()
+    };
+    let branches = git(vec![
+        "branch".into(),
+        "--list".into(),
+        format!("forge/{ingot_id}.*"),
+        "--format=%(refname:short)".into(),
+    ])
+    .await;
+    let worktrees = git(vec!["worktree".into(), "list".into(), "--porcelain".into()]).await;
+
+    let from_branches = branches
+        .lines()
+        .filter_map(|b| b.trim().strip_prefix("forge/"));
+    let from_worktrees = worktrees.lines().filter_map(|l| {
+        let path = l.strip_prefix("worktree ")?;
+        std::path::Path::new(path)
+            .file_name()?
+            .to_str()?
+            .strip_prefix("slag-anvil-")
+    });
+    let mut slots: Vec<String> = from_branches
+        .chain(from_worktrees)
+        .filter(|slot| {
+            slot.strip_prefix(ingot_id)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(str::to_string)
+        .collect();
+    slots.sort();
+    slots.dedup();
+    slots
+}
+
+/// Remove an ingot's worktree and branch along with any fanout candidates'
+pub async fn discard_with_candidates(ingot_id: &str) {
+    for slot in candidate_slots(ingot_id).await {
+        discard(&slot).await;
+    }
+    discard(ingot_id).await;
+}
+
+/// Remove a worktree and its branch (an interrupted attempt that will be redone)
+pub async fn discard(ingot_id: &str) {
+    cleanup_without_merge(ingot_id).await;
+    let _ = tokio::process::Command::new("git")
+        .args(["worktree", "prune"])
+        .output()
+        .await;
+    let _ = tokio::process::Command::new("git")
+        .args(["branch", "-D", &format!("forge/{ingot_id}")])
+        .output()
+        .await;
+}
+
+/// The repository's git directory, shared by all its worktrees: where a
+/// worktree's commits are written
+pub async fn common_dir() -> Option<std::path::PathBuf> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-common-dir"])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!dir.is_empty()).then(|| dir.into())
+}
+
 /// Remove a worktree without merging (failure case, preserves branch for debugging)
 pub async fn cleanup_without_merge(ingot_id: &str) {
     let dir = format!("../slag-anvil-{ingot_id}");
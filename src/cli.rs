@@ -19,9 +19,9 @@ pub struct Cli {
     #[arg(long)]
     pub worktree: bool,
 
-    /// Max parallel anvil workers
-    #[arg(long, default_value_t = crate::config::MAX_ANVILS)]
-    pub anvils: usize,
+    /// Max parallel anvil workers [default: 3]
+    #[arg(long)]
+    pub anvils: Option<usize>,
 
     /// Skip the master review phase (legacy behavior)
     #[arg(long)]
@@ -39,9 +39,37 @@ pub struct Cli {
     #[arg(long)]
     pub review_all: bool,
 
-    /// Max retry cycles when ingots crack (0 = no retry)
-    #[arg(long, default_value_t = 3)]
-    pub retry: usize,
+    /// Max retry cycles when ingots crack (0 = no retry) [default: 3]
+    #[arg(long)]
+    pub retry: Option<usize>,
+
+    /// Stop starting strikes once this many USD are spent
+    #[arg(long, value_name = "USD")]
+    pub budget: Option<f64>,
+
+    /// Keep one agent session per ingot; later heats get only the failure
+    #[arg(long)]
+    pub sessions: bool,
+
+    /// Strike complex ingots N times at once, each in its own worktree
+    #[arg(long, value_name = "N")]
+    pub fanout: Option<u8>,
+
+    /// Let the review smith pick among several passing candidates
+    #[arg(long)]
+    pub fanout_pick: bool,
+
+    /// Run forge smiths, CMD and proof in a sandbox
+    #[arg(long, value_enum, value_name = "BACKEND")]
+    pub sandbox: Option<crate::config::SandboxBackend>,
+
+    /// Write every smith call to a cassette file
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<std::path::PathBuf>,
+
+    /// Answer smith calls from a recorded cassette instead of a backend
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -52,11 +80,81 @@ pub enum Command {
     /// Resume existing forge
     Resume,
 
+    /// Validate the crucible without forging
+    Lint {
+        /// Crucible file to check [default: PLAN.md]
+        file: Option<std::path::PathBuf>,
+    },
+
+    /// Print the crucible as JSON or TOML
+    Export {
+        #[arg(long, value_enum, default_value = "json")]
+        format: crate::crucible::export::Format,
+    },
+
+    /// Write PLAN.md from a JSON or TOML plan
+    Import {
+        /// Plan file (.json or .toml)
+        file: std::path::PathBuf,
+
+        /// Input format (default: from the file extension)
+        #[arg(long, value_enum)]
+        format: Option<crate::crucible::export::Format>,
+
+        /// Overwrite an existing PLAN.md
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Build a replay cassette from a run's log directory
+    Cassette {
+        /// Log directory [default: .slag/logs]
+        logs: Option<std::path::PathBuf>,
+
+        /// Cassette to write
+        #[arg(long, short, default_value = "slag.cassette.jsonl")]
+        out: std::path::PathBuf,
+    },
+
     /// Self-update to latest release
     Update,
 }
 
 impl Cli {
+    /// Layer CLI flags over the configured settings.
+    /// Switch flags can only turn a behavior on.
+    pub fn apply(&self, settings: &mut crate::config::Settings) {
+        if let Some(path) = &self.record {
+            settings.smith.record = Some(path.clone());
+        }
+        if let Some(path) = &self.replay {
+            settings.smith.replay = Some(path.clone());
+        }
+        if let Some(backend) = self.sandbox {
+            settings.sandbox.backend = backend;
+        }
+        let pipeline = &mut settings.pipeline;
+        pipeline.worktree |= self.worktree;
+        pipeline.skip_review |= self.skip_review;
+        pipeline.keep_branches |= self.keep_branches;
+        pipeline.ci_only |= self.ci_only;
+        pipeline.review_all |= self.review_all;
+        pipeline.sessions |= self.sessions;
+        pipeline.fanout_pick |= self.fanout_pick;
+        if let Some(n) = self.anvils {
+            pipeline.max_anvils = n;
+        }
+        if let Some(n) = self.retry {
+            pipeline.max_retry = n;
+        }
+        if let Some(usd) = self.budget {
+            pipeline.budget = usd;
+        }
+        if let Some(n) = self.fanout {
+            pipeline.fanout = n;
+        }
+    }
+
     pub fn commission_text(&self) -> Option<String> {
         if self.commission.is_empty() {
             None
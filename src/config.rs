@@ -1,6 +1,23 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
-/// File paths used by the pipeline
+use serde::{Deserialize, Serialize};
+
+use crate::error::SlagError;
+use crate::smith::adapter::{self, Adapter, Invocation, WEB_TOOLS};
+use crate::smith::cassette::{Cassette, Recorder};
+
+/// Project-level config file
+pub const CONFIG_FILE: &str = "slag.toml";
+
+/// Default state directory; every slag file lives under it
+pub const STATE_DIR: &str = ".slag";
+
+/// Run lock inside the state directory (never committed)
+pub const RUN_LOCK: &str = "run.lock";
+
+/// Default file names inside the state directory (overridable in `[paths]`)
 pub const BLUEPRINT: &str = "BLUEPRINT.md";
 pub const CRUCIBLE: &str = "PLAN.md";
 pub const ORE_FILE: &str = "PRD.md";
@@ -8,46 +25,225 @@ pub const ALLOY_FILE: &str = "AGENTS.md";
 pub const LEDGER: &str = "PROGRESS.md";
 pub const LOG_DIR: &str = "logs";
 
-/// Behavior constants
+/// Default behavior thresholds (overridable in `[pipeline]` / `[thresholds]`)
 pub const MAX_ANVILS: usize = 3;
 pub const HIGH_GRADE: u8 = 3;
 pub const MAX_ITERATE: usize = 3;
 
-/// Smith configuration resolved from environment
+/// Default smith time limits in seconds (overridable in `[timeouts]`)
+pub const STRIKE_TIMEOUT: u64 = 1800;
+pub const PHASE_TIMEOUT: u64 = 900;
+
+/// Defaults for the Messages API backend (overridable in `[api]`)
+pub const API_BASE_URL: &str = "https://api.anthropic.com";
+pub const API_MODEL: &str = "claude-sonnet-4-5";
+pub const API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+
+/// Defaults for the OpenAI-compatible backend (overridable in `[openai]`)
+pub const OPENAI_BASE_URL: &str = "http://localhost:8080/v1";
+pub const OPENAI_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Pipeline phases that invoke a smith
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Survey,
+    Found,
+    Forge,
+    Resmelt,
+    /// Second resmelt of an ingot: rethink the approach from scratch
+    Reconsider,
+    Review,
+    Analysis,
+}
+
+/// How a smith is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Spawn the configured smith command
+    #[default]
+    Cli,
+    /// Call the Messages API over HTTP (`[api]`)
+    Api,
+    /// Call an OpenAI-compatible chat-completions server (`[openai]`)
+    Openai,
+}
+
+/// One row of the routing table (`[[smith.routes]]`): the backend and
+/// model for a phase, or for forge ingots in a grade range
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Route {
+    /// Every phase when unset
+    pub phase: Option<Phase>,
+    /// Inclusive grade bounds; only forge ingots have a grade
+    pub min_grade: Option<u8>,
+    pub max_grade: Option<u8>,
+    /// `[smith] backend` when unset
+    pub backend: Option<Backend>,
+    /// The backend's own model when unset
+    pub model: Option<String>,
+}
+
+impl Route {
+    fn matches(&self, phase: Phase, grade: Option<u8>) -> bool {
+        if self.phase.is_some_and(|p| p != phase) {
+            return false;
+        }
+        match grade {
+            Some(g) => {
+                self.min_grade.is_none_or(|min| g >= min)
+                    && self.max_grade.is_none_or(|max| g <= max)
+            }
+            None => self.min_grade.is_none() && self.max_grade.is_none(),
+        }
+    }
+}
+
+/// Where a smith call goes, as resolved from the routing table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Routed {
+    pub backend: Backend,
+    pub model: Option<String>,
+}
+
+/// Smith invocations per skill/grade and routes per phase, derived from settings
+#[derive(Debug, Clone)]
 pub struct SmithConfig {
-    pub base: String,
-    pub plan: String,
-    pub web: String,
-    pub web_plan: String,
+    pub base: Invocation,
+    pub plan: Invocation,
+    pub web: Invocation,
+    pub web_plan: Invocation,
+    /// The adapter's model flag, `{model}` expanded per route
+    pub model_args: Vec<String>,
+    pub backend: Backend,
+    /// Explicit routes first, then the `[smith.phases]` and `[openai] max_grade` shorthands
+    pub routes: Vec<Route>,
+    pub api: ApiSettings,
+    pub openai: OpenAiSettings,
+    pub timeouts: Timeouts,
+    pub transient: TransientSettings,
+    pub recorder: Option<Arc<Recorder>>,
+    pub cassette: Option<Arc<Cassette>>,
 }
 
 impl SmithConfig {
-    pub fn from_env() -> Self {
-        let base = std::env::var("SLAG_SMITH")
-            .unwrap_or_else(|_| "claude --dangerously-skip-permissions -p".to_string());
-        let plan = format!("{base} --permission-mode plan");
-        let web = format!("{base} --allowedTools 'Bash Edit Read Write Playwright'");
-        let web_plan = format!("{web} --permission-mode plan");
-        Self {
+    /// Resolve the adapter's invocations. Unset plan/web variants are built
+    /// from the base command plus the adapter's plan and tool flags.
+    pub fn from_settings(settings: &Settings) -> Result<Self, SlagError> {
+        let smith = &settings.smith;
+        let adapter = settings.adapters.get(&smith.adapter).ok_or_else(|| {
+            let known: Vec<&str> = settings.adapters.keys().map(String::as_str).collect();
+            SlagError::Config(format!(
+                "unknown adapter '{}' (known: {})",
+                smith.adapter,
+                known.join(", ")
+            ))
+        })?;
+        let argv = |line: &Option<String>| line.as_deref().map(adapter::split_args).transpose();
+
+        let base_argv = argv(&smith.command)?.unwrap_or_else(|| adapter.command.clone());
+        if base_argv.is_empty() {
+            return Err(SlagError::Config(format!(
+                "adapter '{}' has no command",
+                smith.adapter
+            )));
+        }
+        let base = Invocation::new(adapter, base_argv);
+        let plan = match argv(&smith.plan)? {
+            Some(a) => Invocation::new(adapter, a),
+            None => base.clone().with_args(&adapter.plan_args, &[]),
+        };
+        let web = match argv(&smith.web)? {
+            Some(a) => Invocation::new(adapter, a),
+            None => base.clone().with_args(&adapter.tools_args, WEB_TOOLS),
+        };
+        let web_plan = match argv(&smith.web_plan)? {
+            Some(a) => Invocation::new(adapter, a),
+            None => web.clone().with_args(&adapter.plan_args, &[]),
+        };
+
+        let mut routes = smith.routes.clone();
+        if settings.openai.max_grade > 0 {
+            routes.push(Route {
+                phase: Some(Phase::Forge),
+                max_grade: Some(settings.openai.max_grade),
+                backend: Some(Backend::Openai),
+                ..Route::default()
+            });
+        }
+        routes.extend(smith.phases.iter().map(|(phase, backend)| Route {
+            phase: Some(*phase),
+            backend: Some(*backend),
+            ..Route::default()
+        }));
+        if adapter.model_args.is_empty() {
+            let cli_model = routes
+                .iter()
+                .any(|r| r.model.is_some() && r.backend.unwrap_or(smith.backend) == Backend::Cli);
+            if cli_model {
+                return Err(SlagError::Config(format!(
+                    "adapter '{}' has no model_args, so routes cannot pick its model",
+                    smith.adapter
+                )));
+            }
+        }
+
+        Ok(Self {
             base,
             plan,
             web,
             web_plan,
+            model_args: adapter.model_args.clone(),
+            backend: smith.backend,
+            routes,
+            api: settings.api.clone(),
+            openai: settings.openai.clone(),
+            timeouts: settings.timeouts.clone(),
+            transient: settings.transient.clone(),
+            recorder: smith
+                .record
+                .as_deref()
+                .map(Recorder::create)
+                .transpose()?
+                .map(Arc::new),
+            cassette: smith
+                .replay
+                .as_deref()
+                .map(Cassette::load)
+                .transpose()?
+                .map(Arc::new),
+        })
+    }
+
+    /// Backend and model for a phase (`grade` for forge ingots): the first
+    /// matching route, else `backend` with its own model
+    pub fn route(&self, phase: Phase, grade: Option<u8>) -> Routed {
+        match self.routes.iter().find(|r| r.matches(phase, grade)) {
+            Some(route) => Routed {
+                backend: route.backend.unwrap_or(self.backend),
+                model: route.model.clone(),
+            },
+            None => Routed {
+                backend: self.backend,
+                model: None,
+            },
         }
     }
 
-    /// Select smith command based on skill and grade
-    pub fn select(&self, skill: &str, grade: u8) -> &str {
+    /// Select smith invocation based on skill and grade
+    pub fn select(&self, skill: &str, grade: u8) -> &Invocation {
         match skill {
             "web" | "frontend" | "ui" | "css" | "html" => {
-                if grade >= HIGH_GRADE {
+                if grade >= settings().thresholds.high_grade {
                     &self.web_plan
                 } else {
                     &self.web
                 }
             }
             _ => {
-                if grade >= HIGH_GRADE {
+                if grade >= settings().thresholds.high_grade {
                     &self.plan
                 } else {
                     &self.base
@@ -57,13 +253,14 @@ impl SmithConfig {
     }
 }
 
-/// Resolve a project-relative path
+/// Resolve a slag file inside the configured state directory
 pub fn project_path(filename: &str) -> PathBuf {
-    PathBuf::from(filename)
+    Path::new(&paths().state_dir).join(filename)
 }
 
-/// Pipeline execution configuration (from CLI flags)
-#[derive(Debug, Clone, Default)]
+/// Pipeline execution configuration (`[pipeline]`, overridden by CLI flags)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct PipelineConfig {
     /// Enable worktree isolation per ingot
     pub worktree: bool,
@@ -79,32 +276,757 @@ pub struct PipelineConfig {
     pub review_all: bool,
     /// Max retry cycles when ingots crack
     pub max_retry: usize,
+    /// Stop starting strikes once this many USD are spent (0 = no limit)
+    pub budget: f64,
+    /// Keep one agent session per ingot across heats
+    pub sessions: bool,
+    /// Candidates struck at once for complex ingots (0 or 1 = one at a time)
+    pub fanout: u8,
+    /// Let the review smith pick among several passing candidates
+    pub fanout_pick: bool,
 }
 
 impl PipelineConfig {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        worktree: bool,
-        max_anvils: usize,
-        skip_review: bool,
-        keep_branches: bool,
-        ci_only: bool,
-        review_all: bool,
-        max_retry: usize,
-    ) -> Self {
+    /// Check if review phase should run
+    pub fn should_review(&self) -> bool {
+        self.worktree && !self.skip_review
+    }
+}
+
+/// Slag file names, relative to `state_dir` (itself relative to the project root).
+/// Resolve them with `project_path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paths {
+    /// Use "." to keep the files in the project root
+    pub state_dir: String,
+    pub blueprint: String,
+    pub crucible: String,
+    pub ore: String,
+    pub alloy: String,
+    pub ledger: String,
+    pub log_dir: String,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
         Self {
-            worktree,
-            max_anvils,
-            skip_review,
-            keep_branches,
-            ci_only,
-            review_all,
-            max_retry,
+            state_dir: STATE_DIR.into(),
+            blueprint: BLUEPRINT.into(),
+            crucible: CRUCIBLE.into(),
+            ore: ORE_FILE.into(),
+            alloy: ALLOY_FILE.into(),
+            ledger: LEDGER.into(),
+            log_dir: LOG_DIR.into(),
         }
     }
+}
 
-    /// Check if review phase should run
-    pub fn should_review(&self) -> bool {
-        self.worktree && !self.skip_review
+/// Smith selection. `adapter` names an entry in `[adapters]`; `command`
+/// and the plan/web variants override its command lines when set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmithSettings {
+    pub adapter: String,
+    pub command: Option<String>,
+    pub plan: Option<String>,
+    pub web: Option<String>,
+    pub web_plan: Option<String>,
+    /// Backend for every phase no route picks one for
+    pub backend: Backend,
+    /// Routing table (`[[smith.routes]]`); the first matching route wins
+    pub routes: Vec<Route>,
+    /// Per-phase backend shorthand (`[smith.phases]`), tried after `routes`
+    pub phases: BTreeMap<Phase, Backend>,
+    /// Write every smith call to this cassette
+    pub record: Option<PathBuf>,
+    /// Answer every smith call from this cassette instead of a backend
+    pub replay: Option<PathBuf>,
+}
+
+impl Default for SmithSettings {
+    fn default() -> Self {
+        Self {
+            adapter: adapter::DEFAULT_ADAPTER.into(),
+            command: None,
+            plan: None,
+            web: None,
+            web_plan: None,
+            backend: Backend::Cli,
+            routes: Vec::new(),
+            phases: BTreeMap::new(),
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+/// Messages API backend settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiSettings {
+    /// Point at a local stand-in server for tests
+    pub base_url: String,
+    pub model: String,
+    pub max_tokens: u32,
+    /// Env var holding the API key
+    pub api_key_env: String,
+    /// USD per million input and output tokens, for cost accounting
+    pub input_price: f64,
+    pub output_price: f64,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            base_url: API_BASE_URL.into(),
+            model: API_MODEL.into(),
+            max_tokens: 8192,
+            api_key_env: API_KEY_ENV.into(),
+            input_price: 3.0,
+            output_price: 15.0,
+        }
+    }
+}
+
+/// OpenAI-compatible chat-completions backend (llama.cpp, vLLM, Ollama)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAiSettings {
+    /// Including the version prefix, e.g. `http://localhost:11434/v1`
+    pub base_url: String,
+    pub model: String,
+    pub max_tokens: u32,
+    /// Env var holding the API key; local servers usually need none
+    pub api_key_env: String,
+    /// Forge ingots up to this grade here regardless of `[smith]` (0 = off)
+    pub max_grade: u8,
+    /// USD per million input and output tokens; local servers are free
+    pub input_price: f64,
+    pub output_price: f64,
+}
+
+impl Default for OpenAiSettings {
+    fn default() -> Self {
+        Self {
+            base_url: OPENAI_BASE_URL.into(),
+            model: "local".into(),
+            max_tokens: 8192,
+            api_key_env: OPENAI_KEY_ENV.into(),
+            max_grade: 0,
+            input_price: 0.0,
+            output_price: 0.0,
+        }
+    }
+}
+
+/// Smith time limit per invocation in seconds, by phase; 0 means none.
+/// An ingot's `:timeout` overrides `strike`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    pub survey: u64,
+    pub found: u64,
+    pub strike: u64,
+    pub resmelt: u64,
+    pub reconsider: u64,
+    pub review: u64,
+    pub analysis: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            survey: PHASE_TIMEOUT,
+            found: PHASE_TIMEOUT,
+            strike: STRIKE_TIMEOUT,
+            resmelt: PHASE_TIMEOUT,
+            reconsider: PHASE_TIMEOUT,
+            review: PHASE_TIMEOUT,
+            analysis: PHASE_TIMEOUT,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn for_phase(&self, phase: Phase) -> u64 {
+        match phase {
+            Phase::Survey => self.survey,
+            Phase::Found => self.found,
+            Phase::Forge => self.strike,
+            Phase::Resmelt => self.resmelt,
+            Phase::Reconsider => self.reconsider,
+            Phase::Review => self.review,
+            Phase::Analysis => self.analysis,
+        }
+    }
+}
+
+/// Retry and circuit-breaker policy for transient smith failures
+/// (rate limits, overload, network). These retries do not cost a heat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransientSettings {
+    pub retries: u32,
+    /// First backoff delay; doubles per retry up to `max_delay_ms`
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Transient failures in a row, across all anvils, that pause every smith
+    pub breaker_failures: u32,
+    pub breaker_cooldown_secs: u64,
+}
+
+impl Default for TransientSettings {
+    fn default() -> Self {
+        Self {
+            retries: 4,
+            base_delay_ms: 2000,
+            max_delay_ms: 60_000,
+            breaker_failures: 5,
+            breaker_cooldown_secs: 60,
+        }
+    }
+}
+
+/// Tuning knobs for the pipeline phases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Grade at which an ingot counts as complex (plan-mode smith)
+    pub high_grade: u8,
+    /// Self-iteration rounds for surveyor and founder output
+    pub max_iterate: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            high_grade: HIGH_GRADE,
+            max_iterate: MAX_ITERATE,
+        }
+    }
+}
+
+/// How strikes are isolated (`[sandbox]`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    /// Full user privileges
+    #[default]
+    Off,
+    /// Bubblewrap (`bwrap`)
+    Bwrap,
+    /// util-linux `unshare` (2.38+) with user and mount namespaces
+    Unshare,
+}
+
+/// Sandbox for forge smiths and the commands that check their work:
+/// a read-only root, a private /tmp, and only the project (with worktrees,
+/// the ingot's worktree and the git directory) and `writable` open for writing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxSettings {
+    pub backend: SandboxBackend,
+    /// Let CMD and proof reach the network (CLI smiths always can, to reach their model)
+    pub network: bool,
+    /// Also writable, e.g. agent state and tool caches; `~/` is expanded, missing paths skipped
+    pub writable: Vec<String>,
+    /// Per-skill overrides, e.g. `[sandbox.skills.web] network = true`
+    pub skills: BTreeMap<String, SkillSandbox>,
+}
+
+/// One skill's departures from `[sandbox]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SkillSandbox {
+    pub backend: Option<SandboxBackend>,
+    pub network: Option<bool>,
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self {
+            backend: SandboxBackend::Off,
+            network: false,
+            writable: vec![
+                "~/.claude".into(),
+                "~/.claude.json".into(),
+                "~/.cache".into(),
+            ],
+            // Web ingots test against live pages
+            skills: BTreeMap::from([(
+                "web".into(),
+                SkillSandbox {
+                    network: Some(true),
+                    ..SkillSandbox::default()
+                },
+            )]),
+        }
+    }
+}
+
+/// Fully resolved configuration.
+/// Precedence: CLI > env > project `slag.toml` > user `slag.toml` > defaults.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub paths: Paths,
+    pub pipeline: PipelineConfig,
+    pub smith: SmithSettings,
+    pub api: ApiSettings,
+    pub openai: OpenAiSettings,
+    pub adapters: BTreeMap<String, Adapter>,
+    pub timeouts: Timeouts,
+    pub transient: TransientSettings,
+    pub thresholds: Thresholds,
+    pub sandbox: SandboxSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            paths: Paths::default(),
+            pipeline: PipelineConfig {
+                max_anvils: MAX_ANVILS,
+                max_retry: 3,
+                ..PipelineConfig::default()
+            },
+            smith: SmithSettings::default(),
+            api: ApiSettings::default(),
+            openai: OpenAiSettings::default(),
+            adapters: adapter::builtin(),
+            timeouts: Timeouts::default(),
+            transient: TransientSettings::default(),
+            thresholds: Thresholds::default(),
+            sandbox: SandboxSettings::default(),
+        }
+    }
+}
+
+/// One `slag.toml` layer: every key optional, unknown keys rejected
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    paths: Option<toml::Table>,
+    pipeline: Option<toml::Table>,
+    smith: Option<toml::Table>,
+    api: Option<toml::Table>,
+    openai: Option<toml::Table>,
+    adapters: Option<toml::Table>,
+    timeouts: Option<toml::Table>,
+    transient: Option<toml::Table>,
+    thresholds: Option<toml::Table>,
+    sandbox: Option<toml::Table>,
+}
+
+impl Settings {
+    /// Defaults, then the user file, then the project file, then env vars.
+    /// CLI flags are applied by the caller on top.
+    pub fn load() -> Result<Self, SlagError> {
+        let mut settings = Settings::default();
+        if let Some(path) = user_config_path() {
+            settings.apply_file(&path)?;
+        }
+        settings.apply_file(Path::new(CONFIG_FILE))?;
+        settings.apply_env(|key| std::env::var(key).ok())?;
+        Ok(settings)
+    }
+
+    /// Layer a config file over the current values; a missing file is skipped
+    fn apply_file(&mut self, path: &Path) -> Result<(), SlagError> {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Ok(());
+        };
+        self.apply_toml(&content)
+            .map_err(|e| SlagError::Config(format!("{}: {e}", path.display())))
+    }
+
+    fn apply_toml(&mut self, content: &str) -> Result<(), String> {
+        let file: ConfigFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        if let Some(table) = file.paths {
+            self.paths = overlay(&self.paths, table)?;
+        }
+        if let Some(table) = file.pipeline {
+            self.pipeline = overlay(&self.pipeline, table)?;
+        }
+        if let Some(table) = file.smith {
+            self.smith = overlay(&self.smith, table)?;
+        }
+        if let Some(table) = file.api {
+            self.api = overlay(&self.api, table)?;
+        }
+        if let Some(table) = file.openai {
+            self.openai = overlay(&self.openai, table)?;
+        }
+        if let Some(table) = file.timeouts {
+            self.timeouts = overlay(&self.timeouts, table)?;
+        }
+        if let Some(table) = file.transient {
+            self.transient = overlay(&self.transient, table)?;
+        }
+        // Per adapter, so a file can tweak one key of a built-in
+        for (name, value) in file.adapters.unwrap_or_default() {
+            let toml::Value::Table(table) = value else {
+                return Err(format!("adapters.{name} must be a table"));
+            };
+            let base = self.adapters.get(&name).cloned().unwrap_or_default();
+            let adapter = overlay(&base, table).map_err(|e| format!("adapters.{name}: {e}"))?;
+            self.adapters.insert(name, adapter);
+        }
+        if let Some(table) = file.thresholds {
+            self.thresholds = overlay(&self.thresholds, table)?;
+        }
+        if let Some(mut table) = file.sandbox {
+            // Per skill too, so `[sandbox.skills.web]` keeps the other skills
+            let skills = table.remove("skills");
+            self.sandbox = overlay(&self.sandbox, table)?;
+            if let Some(skills) = skills {
+                let toml::Value::Table(skills) = skills else {
+                    return Err("sandbox.skills must be a table".into());
+                };
+                for (name, value) in skills {
+                    let toml::Value::Table(table) = value else {
+                        return Err(format!("sandbox.skills.{name} must be a table"));
+                    };
+                    let base = self.sandbox.skills.get(&name).cloned().unwrap_or_default();
+                    let skill =
+                        overlay(&base, table).map_err(|e| format!("sandbox.skills.{name}: {e}"))?;
+                    self.sandbox.skills.insert(name, skill);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `SLAG_SMITH`, `SLAG_ANVILS` and `SLAG_RETRY`
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), SlagError> {
+        if let Some(cmd) = var("SLAG_SMITH") {
+            self.smith.command = Some(cmd);
+        }
+        if let Some(n) = var("SLAG_ANVILS") {
+            self.pipeline.max_anvils = parse_env("SLAG_ANVILS", &n)?;
+        }
+        if let Some(n) = var("SLAG_RETRY") {
+            self.pipeline.max_retry = parse_env("SLAG_RETRY", &n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Apply the keys present in `table` on top of `base`
+fn overlay<T>(base: &T, table: toml::Table) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned + Serialize,
+{
+    let mut merged = toml::Table::try_from(base).map_err(|e| e.to_string())?;
+    merged.extend(table);
+    merged
+        .try_into()
+        .map_err(|e: toml::de::Error| e.to_string())
+}
+
+fn parse_env(key: &str, value: &str) -> Result<usize, SlagError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| SlagError::Config(format!("{key} must be a number, got '{value}'")))
+}
+
+/// `$XDG_CONFIG_HOME/slag/slag.toml`, falling back to `~/.config/slag/slag.toml`
+fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("slag").join(CONFIG_FILE))
+}
+
+/// Move a pre-`.slag/` layout from the project root into the state directory.
+///
+/// Only runs when the root holds a slag crucible or ledger, and only moves
+/// files slag created, so a project's own PRD.md or AGENTS.md stays put.
+/// Returns the names that were moved.
+pub fn migrate_root_layout(root: &Path, paths: &Paths) -> std::io::Result<Vec<String>> {
+    let state = root.join(&paths.state_dir);
+    if state == root || state == root.join(".") {
+        return Ok(Vec::new());
+    }
+
+    let starts_with = |name: &str, marker: &str| {
+        std::fs::read_to_string(root.join(name))
+            .map(|c| c.trim_start().starts_with(marker))
+            .unwrap_or(false)
+    };
+    let legacy = starts_with(&paths.crucible, ";; CRUCIBLE")
+        || starts_with(&paths.ledger, "# Smithy Ledger");
+    if !legacy {
+        return Ok(Vec::new());
+    }
+
+    let candidates = [
+        (&paths.crucible, true),
+        (&paths.ledger, true),
+        (&paths.blueprint, true),
+        (&paths.ore, starts_with(&paths.ore, "# Commission")),
+        (&paths.alloy, starts_with(&paths.alloy, "## Alloy Recipes")),
+        (&paths.log_dir, true),
+    ];
+
+    std::fs::create_dir_all(&state)?;
+    let mut moved = Vec::new();
+    for (name, ours) in candidates {
+        let from = root.join(name);
+        let to = state.join(name);
+        if ours && from.exists() && !to.exists() {
+            std::fs::rename(&from, &to)?;
+            moved.push(name.clone());
+        }
+    }
+    Ok(moved)
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Install the resolved settings for the rest of the run (first call wins)
+pub fn init(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Active settings; defaults if `init` was never called (e.g. in tests)
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Active file paths
+pub fn paths() -> &'static Paths {
+    &settings().paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_overrides_user_and_keeps_defaults() {
+        let mut s = Settings::default();
+        s.apply_toml("[pipeline]\nmax_anvils = 6\nworktree = true\n[smith]\ncommand = \"aider\"\n")
+            .unwrap();
+        s.apply_toml("[pipeline]\nmax_anvils = 2\n[paths]\ncrucible = \"plan/PLAN.md\"\n")
+            .unwrap();
+
+        assert_eq!(s.pipeline.max_anvils, 2);
+        assert!(s.pipeline.worktree);
+        assert_eq!(s.pipeline.max_retry, 3);
+        assert_eq!(s.smith.command.as_deref(), Some("aider"));
+        assert_eq!(s.paths.crucible, "plan/PLAN.md");
+        assert_eq!(s.paths.ledger, LEDGER);
+        assert_eq!(s.thresholds.high_grade, HIGH_GRADE);
+    }
+
+    #[test]
+    fn env_overrides_files() {
+        let mut s = Settings::default();
+        s.apply_toml("[pipeline]\nmax_anvils = 6\n[smith]\ncommand = \"aider\"\n")
+            .unwrap();
+        s.apply_env(|key| match key {
+            "SLAG_SMITH" => Some("claude -p".into()),
+            "SLAG_ANVILS" => Some("1".into()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(s.smith.command.as_deref(), Some("claude -p"));
+        assert_eq!(s.pipeline.max_anvils, 1);
+        assert!(s.apply_env(|_| Some("many".into())).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let mut s = Settings::default();
+        assert!(s.apply_toml("[pipeline]\nmax_anvil = 2\n").is_err());
+        assert!(s.apply_toml("[pipline]\nmax_anvils = 2\n").is_err());
+        assert!(s.apply_toml("[pipeline]\nmax_anvils = \"two\"\n").is_err());
+    }
+
+    #[test]
+    fn migrates_slag_files_only() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| std::fs::write(root.path().join(name), content);
+        write("PLAN.md", ";; CRUCIBLE 2026-01-27\n").unwrap();
+        write("PROGRESS.md", "# Smithy Ledger\n").unwrap();
+        write("PRD.md", "# Commission\n\nBuild it\n").unwrap();
+        write("AGENTS.md", "# Team agents guide\n").unwrap();
+        std::fs::create_dir(root.path().join("logs")).unwrap();
+
+        let paths = Paths::default();
+        let moved = migrate_root_layout(root.path(), &paths).unwrap();
+        assert_eq!(moved, ["PLAN.md", "PROGRESS.md", "PRD.md", "logs"]);
+
+        let state = root.path().join(STATE_DIR);
+        assert!(state.join("PLAN.md").exists());
+        assert!(state.join("logs").is_dir());
+        assert!(root.path().join("AGENTS.md").exists());
+        assert!(!state.join("AGENTS.md").exists());
+
+        // Second run finds nothing left to move
+        assert!(migrate_root_layout(root.path(), &paths).unwrap().is_empty());
+    }
+
+    #[test]
+    fn leaves_foreign_plan_alone() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("PLAN.md"), "# Our roadmap\n").unwrap();
+        let moved = migrate_root_layout(root.path(), &Paths::default()).unwrap();
+        assert!(moved.is_empty());
+        assert!(root.path().join("PLAN.md").exists());
+    }
+
+    #[test]
+    fn smith_variants_derive_from_command() {
+        let settings = Settings {
+            smith: SmithSettings {
+                command: Some("claude -p".into()),
+                web_plan: Some("claude -p --web-plan".into()),
+                ..SmithSettings::default()
+            },
+            ..Settings::default()
+        };
+        let cfg = SmithConfig::from_settings(&settings).unwrap();
+        assert_eq!(cfg.plan.argv, ["claude", "-p", "--permission-mode", "plan"]);
+        assert_eq!(cfg.web.argv[2], "--allowedTools");
+        assert_eq!(cfg.web_plan.argv, ["claude", "-p", "--web-plan"]);
+    }
+
+    #[test]
+    fn backend_per_phase() {
+        let mut s = Settings::default();
+        s.apply_toml("[smith.phases]\nsurvey = \"api\"\nfound = \"api\"\n[api]\nmodel = \"m\"\n")
+            .unwrap();
+
+        let cfg = SmithConfig::from_settings(&s).unwrap();
+        let backend = |phase| cfg.route(phase, None).backend;
+        assert_eq!(backend(Phase::Survey), Backend::Api);
+        assert_eq!(backend(Phase::Found), Backend::Api);
+        assert_eq!(backend(Phase::Forge), Backend::Cli);
+        assert_eq!(cfg.api.model, "m");
+        assert_eq!(cfg.api.base_url, API_BASE_URL);
+        assert_eq!(cfg.timeouts.for_phase(Phase::Forge), STRIKE_TIMEOUT);
+        assert!(s.apply_toml("[smith.phases]\nsmelt = \"api\"\n").is_err());
+        assert!(s.apply_toml("[smith]\nbackend = \"http\"\n").is_err());
+    }
+
+    #[test]
+    fn low_grades_forge_on_openai() {
+        let mut s = Settings::default();
+        s.apply_toml("[smith.phases]\nforge = \"api\"\n[openai]\nmax_grade = 1\n")
+            .unwrap();
+        let cfg = SmithConfig::from_settings(&s).unwrap();
+        assert_eq!(cfg.route(Phase::Forge, Some(1)).backend, Backend::Openai);
+        assert_eq!(cfg.route(Phase::Forge, Some(2)).backend, Backend::Api);
+        assert_eq!(
+            SmithConfig::from_settings(&Settings::default())
+                .unwrap()
+                .route(Phase::Forge, Some(1))
+                .backend,
+            Backend::Cli
+        );
+    }
+
+    #[test]
+    fn routes_pick_backend_and_model() {
+        let mut s = Settings::default();
+        s.apply_toml(
+            "[smith.phases]\nreview = \"api\"\n\
+             [[smith.routes]]\nphase = \"survey\"\nmodel = \"opus\"\n\
+             [[smith.routes]]\nphase = \"forge\"\nmax_grade = 1\nbackend = \"openai\"\nmodel = \"qwen\"\n\
+             [[smith.routes]]\nphase = \"review\"\nbackend = \"api\"\nmodel = \"reviewer\"\n",
+        )
+        .unwrap();
+        let cfg = SmithConfig::from_settings(&s).unwrap();
+
+        let routed = |backend, model: Option<&str>| Routed {
+            backend,
+            model: model.map(String::from),
+        };
+        assert_eq!(
+            cfg.route(Phase::Survey, None),
+            routed(Backend::Cli, Some("opus"))
+        );
+        assert_eq!(
+            cfg.route(Phase::Forge, Some(1)),
+            routed(Backend::Openai, Some("qwen"))
+        );
+        assert_eq!(cfg.route(Phase::Forge, Some(2)), routed(Backend::Cli, None));
+        // Explicit routes win over the `[smith.phases]` shorthand
+        assert_eq!(
+            cfg.route(Phase::Review, None),
+            routed(Backend::Api, Some("reviewer"))
+        );
+        assert_eq!(
+            cfg.route(Phase::Reconsider, None),
+            routed(Backend::Cli, None)
+        );
+        assert_eq!(cfg.model_args, ["--model", "{model}"]);
+
+        // A CLI route's model needs an adapter that can take one
+        s.adapters.get_mut("claude").unwrap().model_args.clear();
+        assert!(SmithConfig::from_settings(&s).is_err());
+        assert!(s
+            .apply_toml("[[smith.routes]]\nphase = \"forge\"\ngrade = 1\n")
+            .is_err());
+    }
+
+    #[test]
+    fn adapters_extend_and_tweak_builtins() {
+        let mut s = Settings::default();
+        s.apply_toml(
+            "[smith]\nadapter = \"aider\"\n\
+             [adapters.aider]\ncommand = [\"aider\", \"--model\", \"sonnet\"]\n\
+             [adapters.mine]\ncommand = [\"mine\"]\nprompt = \"arg\"\noutput = { json = \"answer\" }\n",
+        )
+        .unwrap();
+
+        let aider = &s.adapters["aider"];
+        assert_eq!(aider.prompt_flag.as_deref(), Some("--message-file"));
+        let cfg = SmithConfig::from_settings(&s).unwrap();
+        assert_eq!(cfg.base.argv, ["aider", "--model", "sonnet"]);
+        assert_eq!(cfg.plan.argv[3..], ["--chat-mode", "ask"]);
+        // No tool allow-list: web is the base command
+        assert_eq!(cfg.web.argv, cfg.base.argv);
+        assert_eq!(
+            s.adapters["mine"].output,
+            adapter::Extract::Json("answer".into())
+        );
+
+        s.smith.adapter = "nope".into();
+        assert!(SmithConfig::from_settings(&s).is_err());
+        assert!(s
+            .apply_toml("[adapters.mine]\nprompt = \"pipe\"\n")
+            .is_err());
+    }
+
+    #[test]
+    fn sandbox_skills_merge_into_the_defaults() {
+        let mut s = Settings::default();
+        assert_eq!(s.sandbox.backend, SandboxBackend::Off);
+        assert_eq!(s.sandbox.skills["web"].network, Some(true));
+
+        s.apply_toml(
+            "[sandbox]\nbackend = \"unshare\"\nwritable = [\"~/.cargo\"]\n\
+             [sandbox.skills.docker]\nbackend = \"off\"\n",
+        )
+        .unwrap();
+        assert_eq!(s.sandbox.backend, SandboxBackend::Unshare);
+        assert_eq!(s.sandbox.writable, ["~/.cargo"]);
+        assert_eq!(
+            s.sandbox.skills.keys().collect::<Vec<_>>(),
+            [&"docker".to_string(), &"web".to_string()]
+        );
+        assert_eq!(s.sandbox.skills["web"].network, Some(true));
+
+        s.apply_toml("[sandbox.skills.web]\nbackend = \"bwrap\"\n")
+            .unwrap();
+        assert_eq!(s.sandbox.skills["web"].backend, Some(SandboxBackend::Bwrap));
+        assert_eq!(s.sandbox.skills["web"].network, Some(true));
+        assert!(s.apply_toml("[sandbox.skills.web]\nnet = true\n").is_err());
     }
 }
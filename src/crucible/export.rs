@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::Crucible;
+use crate::error::SlagError;
+use crate::sexp::parser::KNOWN_FIELDS;
+use crate::sexp::{Ingot, Skill, Status};
+
+/// Interchange format for `slag export` / `slag import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Guess the format from a file extension (`.toml`, otherwise JSON)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+}
+
+/// The whole crucible as plain data: header lines plus ingots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanDoc {
+    #[serde(default)]
+    pub header: Vec<String>,
+    #[serde(default)]
+    pub ingots: Vec<IngotDoc>,
+}
+
+/// One ingot as plain data. Omitted fields take the same defaults as PLAN.md.
+/// `extra` carries unknown fields; key order is normalized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngotDoc {
+    pub id: String,
+    #[serde(default = "default_status")]
+    pub status: String,
+    #[serde(default = "default_solo")]
+    pub solo: bool,
+    #[serde(default)]
+    pub deps: Vec<String>,
+    #[serde(default = "default_grade")]
+    pub grade: u8,
+    #[serde(default = "default_skill")]
+    pub skill: String,
+    #[serde(default)]
+    pub heat: u8,
+    #[serde(default = "default_max")]
+    pub max: u8,
+    #[serde(default)]
+    pub smelt: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fanout: Option<u8>,
+    #[serde(default = "default_proof")]
+    pub proof: String,
+    #[serde(default)]
+    pub work: String,
+    #[serde(default)]
+    pub extra: BTreeMap<String, String>,
+}
+
+fn default_status() -> String {
+    Status::Ore.as_str().to_string()
+}
+
+fn default_solo() -> bool {
+    true
+}
+
+fn default_grade() -> u8 {
+    1
+}
+
+fn default_skill() -> String {
+    Skill::Default.as_str().to_string()
+}
+
+fn default_max() -> u8 {
+    5
+}
+
+fn default_proof() -> String {
+    "true".into()
+}
+
+impl From<&Ingot> for IngotDoc {
+    fn from(ingot: &Ingot) -> Self {
+        IngotDoc {
+            id: ingot.id.clone(),
+            status: ingot.status.as_str().to_string(),
+            solo: ingot.solo,
+            deps: ingot.deps.clone(),
+            grade: ingot.grade,
+            skill: ingot.skill.as_str().to_string(),
+            heat: ingot.heat,
+            max: ingot.max,
+            smelt: ingot.smelt,
+            timeout: ingot.timeout,
+            fanout: ingot.fanout,
+            proof: ingot.proof.clone(),
+            work: ingot.work.clone(),
+            extra: ingot.extra.iter().cloned().collect(),
+        }
+    }
+}
+
+impl IngotDoc {
+    fn into_ingot(self) -> Result<Ingot, String> {
+        let status = Status::parse(&self.status)
+            .ok_or_else(|| format!("[{}] unknown status '{}'", self.id, self.status))?;
+        let skill = Skill::parse(&self.skill)
+            .ok_or_else(|| format!("[{}] unknown skill '{}'", self.id, self.skill))?;
+        for key in self.extra.keys() {
+            if KNOWN_FIELDS.contains(&key.as_str()) {
+                return Err(format!("[{}] extra field '{key}' shadows :{key}", self.id));
+            }
+            let bare = !key.is_empty()
+                && !key
+                    .chars()
+                    .any(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"' | ';' | ':'));
+            if !bare {
+                return Err(format!(
+                    "[{}] extra field '{key}' is not a valid key",
+                    self.id
+                ));
+            }
+        }
+        Ok(Ingot {
+            id: self.id,
+            status,
+            solo: self.solo,
+            deps: self.deps,
+            grade: self.grade,
+            skill,
+            heat: self.heat,
+            max: self.max,
+            smelt: self.smelt,
+            timeout: self.timeout,
+            fanout: self.fanout,
+            proof: self.proof,
+            work: self.work,
+            extra: self.extra.into_iter().collect(),
+        })
+    }
+}
+
+impl Crucible {
+    /// Plain-data view of the crucible, for export
+    pub fn to_doc(&self) -> PlanDoc {
+        PlanDoc {
+            header: self.header_lines.clone(),
+            ingots: self.ingots.iter().map(IngotDoc::from).collect(),
+        }
+    }
+
+    /// Build a crucible from plain data. The result is rendered and re-read as
+    /// PLAN.md, so it must also pass lint before it can be saved.
+    pub async fn from_doc(path: &Path, doc: PlanDoc) -> Result<Self, SlagError> {
+        let ingots = doc
+            .ingots
+            .into_iter()
+            .map(IngotDoc::into_ingot)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SlagError::CrucibleParse)?;
+        let staged = Crucible {
+            path: path.to_path_buf(),
+            header_lines: doc.header,
+            ingots,
+            removed: HashSet::new(),
+        };
+
+        let content = staged.build_content();
+        let report = crate::lint::lint_content(&content).await;
+        if report.errors() > 0 {
+            crate::lint::print_report(path, &report);
+            return Err(SlagError::LintFailed(report.errors()));
+        }
+
+        // Header lines that look like ingots would not survive the round trip
+        let crucible = Crucible::from_content(path, &content)?;
+        if crucible.ingots != staged.ingots {
+            return Err(SlagError::CrucibleParse(
+                "header lines must not contain ingot forms".into(),
+            ));
+        }
+        Ok(crucible)
+    }
+}
+
+/// Serialize a plan document in the given format
+pub fn render(doc: &PlanDoc, format: Format) -> Result<String, SlagError> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(doc)
+            .map(|s| s + "\n")
+            .map_err(|e| SlagError::Other(e.into())),
+        Format::Toml => toml::to_string(doc).map_err(|e| SlagError::Other(e.into())),
+    }
+}
+
+/// Parse a plan document in the given format
+pub fn parse(content: &str, format: Format) -> Result<PlanDoc, SlagError> {
+    match format {
+        Format::Json => serde_json::from_str(content)
+            .map_err(|e| SlagError::CrucibleParse(format!("invalid JSON plan: {e}"))),
+        Format::Toml => toml::from_str(content)
+            .map_err(|e| SlagError::CrucibleParse(format!("invalid TOML plan: {e}"))),
+    }
+}
+
+/// `slag export`: print the crucible as JSON or TOML on stdout
+pub fn export(crucible_path: &Path, format: Format) -> Result<(), SlagError> {
+    let crucible = Crucible::load(crucible_path)?;
+    print!("{}", render(&crucible.to_doc(), format)?);
+    Ok(())
+}
+
+/// `slag import`: read a JSON or TOML plan and write it as PLAN.md.
+/// Refuses to overwrite an existing crucible unless `force` is set.
+pub async fn import(
+    source: &Path,
+    format: Option<Format>,
+    crucible_path: &Path,
+    force: bool,
+) -> Result<(), SlagError> {
+    if crucible_path.exists() && !force {
+        return Err(SlagError::Io(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists (use --force to overwrite)",
+                crucible_path.display()
+            ),
+        )));
+    }
+
+    let content = std::fs::read_to_string(source)?;
+    let doc = parse(
+        &content,
+        format.unwrap_or_else(|| Format::from_path(source)),
+    )?;
+    let mut crucible = Crucible::from_doc(crucible_path, doc).await?;
+    crucible.save()?;
+
+    println!(
+        "\n  \x1b[1;37m█\x1b[0m imported {} ingots into {}\n",
+        crucible.ingots.len(),
+        crucible_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#";; CRUCIBLE 2026-01-27
+;; Blueprint: PRD.md
+(ingot :id "i1" :status forged :solo t :grade 1 :skill web :heat 1 :max 5 :smelt 0 :proof "test -f index.html" :work "Create \"index\" page" :owner ana)
+(ingot :id "i2" :status ore :solo nil :deps ("i1") :grade 3 :skill api :heat 0 :max 8 :smelt 1 :proof "grep -q ok out.txt" :work "Wire the API" :note "two words")
+"#;
+
+    fn crucible() -> Crucible {
+        Crucible::from_content(Path::new("PLAN.md"), PLAN).unwrap()
+    }
+
+    #[tokio::test]
+    async fn json_roundtrip() {
+        let original = crucible();
+        let json = render(&original.to_doc(), Format::Json).unwrap();
+        assert!(json.contains(r#""owner": "ana""#));
+
+        let back = Crucible::from_doc(Path::new("PLAN.md"), parse(&json, Format::Json).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(back.ingots, original.ingots);
+        assert_eq!(back.build_content(), original.build_content());
+    }
+
+    #[tokio::test]
+    async fn toml_roundtrip() {
+        let original = crucible();
+        let text = render(&original.to_doc(), Format::Toml).unwrap();
+        assert!(text.contains("[[ingots]]"));
+
+        let back = Crucible::from_doc(Path::new("PLAN.md"), parse(&text, Format::Toml).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(back.build_content(), original.build_content());
+    }
+
+    #[tokio::test]
+    async fn import_fills_defaults() {
+        let doc = parse(
+            r#"{"ingots": [{"id": "a", "proof": "test -f a", "work": "Make a"}]}"#,
+            Format::Json,
+        )
+        .unwrap();
+        let c = Crucible::from_doc(Path::new("PLAN.md"), doc).await.unwrap();
+        let a = c.get("a").unwrap();
+        assert_eq!(a.status, Status::Ore);
+        assert!(a.solo);
+        assert_eq!((a.grade, a.max), (1, 5));
+    }
+
+    #[tokio::test]
+    async fn import_rejects_invalid_data() {
+        let bad_status = r#"{"ingots": [{"id": "a", "status": "hot"}]}"#;
+        let doc = parse(bad_status, Format::Json).unwrap();
+        assert!(Crucible::from_doc(Path::new("PLAN.md"), doc).await.is_err());
+
+        let bad_dep = r#"{"ingots": [{"id": "a", "deps": ["zz"], "proof": "test -f a"}]}"#;
+        let doc = parse(bad_dep, Format::Json).unwrap();
+        assert!(matches!(
+            Crucible::from_doc(Path::new("PLAN.md"), doc).await,
+            Err(SlagError::LintFailed(1))
+        ));
+
+        let bad_skill = r#"{"ingots": [{"id": "a", "skill": "docker"}]}"#;
+        let doc = parse(bad_skill, Format::Json).unwrap();
+        let err = Crucible::from_doc(Path::new("PLAN.md"), doc).await;
+        assert!(
+            matches!(&err, Err(SlagError::CrucibleParse(m)) if m.contains("unknown skill 'docker'")),
+            "{err:?}"
+        );
+
+        let shadow = r#"{"ingots": [{"id": "a", "extra": {"grade": "9"}}]}"#;
+        let doc = parse(shadow, Format::Json).unwrap();
+        assert!(Crucible::from_doc(Path::new("PLAN.md"), doc).await.is_err());
+    }
+}
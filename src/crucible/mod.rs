@@ -0,0 +1,738 @@
+pub mod export;
+pub mod service;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::SlagError;
+use crate::sexp::parser::scan_crucible;
+use crate::sexp::writer::write_ingot;
+use crate::sexp::{Ingot, Status, SESSION_FIELD};
+
+/// In-memory representation of PLAN.md (the crucible file).
+/// All mutations happen here, then flush to disk.
+/// During a forge the authoritative copy lives in `service::CrucibleService`.
+#[derive(Debug, Clone)]
+pub struct Crucible {
+    pub path: PathBuf,
+    /// Raw header lines (comments, metadata)
+    header_lines: Vec<String>,
+    pub ingots: Vec<Ingot>,
+    /// Ids replaced or split away since the last save, so an external copy
+    /// of PLAN.md that still lists them cannot bring them back
+    removed: HashSet<String>,
+}
+
+impl Crucible {
+    /// Load crucible from a PLAN.md file.
+    /// Rejects dependency graphs with unknown ids or cycles.
+    pub fn load(path: &Path) -> Result<Self, SlagError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_content(path, &content)
+    }
+
+    /// Parse crucible state from PLAN.md content (path is where it saves to).
+    /// Malformed ingots are errors, reported with line and column.
+    pub fn from_content(path: &Path, content: &str) -> Result<Self, SlagError> {
+        let scan = scan_crucible(content);
+        if let Some(e) = scan.errors.first() {
+            return Err(SlagError::CrucibleParse(format!("{}: {e}", path.display())));
+        }
+
+        let crucible = Crucible {
+            path: path.to_path_buf(),
+            header_lines: scan.header_lines,
+            ingots: scan.ingots.into_iter().map(|s| s.ingot).collect(),
+            removed: HashSet::new(),
+        };
+        crucible.validate_deps()?;
+        Ok(crucible)
+    }
+
+    /// Create a new crucible with header and ingots
+    pub fn new(path: &Path, ingots: Vec<Ingot>) -> Self {
+        let header_lines = vec![
+            format!(
+                ";; CRUCIBLE {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M")
+            ),
+            format!(
+                ";; Blueprint: {}",
+                crate::config::project_path(&crate::config::paths().blueprint).display()
+            ),
+        ];
+        Crucible {
+            path: path.to_path_buf(),
+            header_lines,
+            ingots,
+            removed: HashSet::new(),
+        }
+    }
+
+    /// Build content string from crucible state
+    fn build_content(&self) -> String {
+        let mut content = String::new();
+        for line in &self.header_lines {
+            content.push_str(line);
+            content.push('\n');
+        }
+        for ingot in &self.ingots {
+            content.push_str(&write_ingot(ingot));
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Save crucible to disk atomically (write to temp, then rename)
+    /// This prevents race conditions with git add -A during parallel anvils
+    pub fn save(&mut self) -> Result<(), std::io::Error> {
+        let content = self.build_content();
+
+        // Write to temp file in same directory (ensures same filesystem for atomic rename)
+        let temp_path = self.path.with_extension("tmp");
+        std::fs::write(&temp_path, &content)?;
+
+        // Atomic rename
+        std::fs::rename(&temp_path, &self.path)?;
+        self.removed.clear();
+        Ok(())
+    }
+
+    /// Async save atomically (for use in tokio context)
+    pub async fn save_async(&mut self) -> Result<(), std::io::Error> {
+        let content = self.build_content();
+
+        // Write to temp file in same directory
+        let temp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&temp_path, &content).await?;
+
+        // Atomic rename
+        tokio::fs::rename(&temp_path, &self.path).await?;
+        self.removed.clear();
+        Ok(())
+    }
+
+    /// Find ingot by id
+    pub fn get(&self, id: &str) -> Option<&Ingot> {
+        self.ingots.iter().find(|i| i.id == id)
+    }
+
+    /// Find mutable ingot by id
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Ingot> {
+        self.ingots.iter_mut().find(|i| i.id == id)
+    }
+
+    /// Set status for an ingot by id
+    pub fn set_status(&mut self, id: &str, status: Status) {
+        if let Some(ingot) = self.get_mut(id) {
+            ingot.status = status;
+        }
+    }
+
+    /// Increment heat for an ingot
+    pub fn increment_heat(&mut self, id: &str) {
+        if let Some(ingot) = self.get_mut(id) {
+            ingot.heat += 1;
+        }
+    }
+
+    /// Set or remove an extra field for an ingot
+    pub fn set_extra(&mut self, id: &str, key: &str, value: Option<&str>) {
+        if let Some(ingot) = self.get_mut(id) {
+            ingot.set_extra_field(key, value);
+        }
+    }
+
+    /// Get next ore ingot (any)
+    pub fn next_ore(&self) -> Option<&Ingot> {
+        self.ingots.iter().find(|i| i.status == Status::Ore)
+    }
+
+    /// Whether every dependency of an ingot has been forged
+    pub fn deps_met(&self, ingot: &Ingot) -> bool {
+        ingot.deps.iter().all(|dep| {
+            self.get(dep)
+                .map(|d| d.status == Status::Forged)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Get ore ingots that may start right now, alongside each other.
+    /// With `:deps`, an ingot is ready once all its deps are forged.
+    /// Without `:deps`, only `:solo t` ingots are ready (legacy semantics).
+    pub fn ready_ore(&self) -> Vec<&Ingot> {
+        self.ingots
+            .iter()
+            .filter(|i| i.status == Status::Ore)
+            .filter(|i| {
+                if i.has_deps() {
+                    self.deps_met(i)
+                } else {
+                    i.solo
+                }
+            })
+            .collect()
+    }
+
+    /// Get ore ingots that can never start in this run because a dependency cracked
+    pub fn blocked_ore(&self) -> Vec<&Ingot> {
+        self.ingots
+            .iter()
+            .filter(|i| i.status == Status::Ore && i.has_deps())
+            .filter(|i| {
+                i.deps.iter().any(|dep| {
+                    self.get(dep)
+                        .map(|d| d.status == Status::Cracked)
+                        .unwrap_or(true)
+                })
+            })
+            .collect()
+    }
+
+    /// Check that every `:deps` entry names a known ingot and the graph is acyclic
+    pub fn validate_deps(&self) -> Result<(), SlagError> {
+        let ids: HashSet<&str> = self.ingots.iter().map(|i| i.id.as_str()).collect();
+        for ingot in &self.ingots {
+            for dep in &ingot.deps {
+                if !ids.contains(dep.as_str()) {
+                    return Err(SlagError::CrucibleParse(format!(
+                        "ingot {} depends on unknown ingot {dep}",
+                        ingot.id
+                    )));
+                }
+            }
+        }
+
+        if let Some(cycle) = self.find_cycle() {
+            return Err(SlagError::CrucibleParse(format!(
+                "dependency cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Find a dependency cycle, returned as the ids along it (first id repeated at the end)
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Visiting,
+            Done,
+        }
+
+        fn visit<'a>(
+            id: &'a str,
+            graph: &HashMap<&'a str, &'a [String]>,
+            marks: &mut HashMap<&'a str, Mark>,
+            stack: &mut Vec<&'a str>,
+        ) -> Option<Vec<String>> {
+            match marks.get(id) {
+                Some(Mark::Done) => return None,
+                Some(Mark::Visiting) => {
+                    let start = stack.iter().position(|s| *s == id).unwrap_or(0);
+                    let mut cycle: Vec<String> =
+                        stack[start..].iter().map(|s| s.to_string()).collect();
+                    cycle.push(id.to_string());
+                    return Some(cycle);
+                }
+                None => {}
+            }
+
+            marks.insert(id, Mark::Visiting);
+            stack.push(id);
+            for dep in graph.get(id).copied().unwrap_or(&[]) {
+                if let Some(cycle) = visit(dep, graph, marks, stack) {
+                    return Some(cycle);
+                }
+            }
+            stack.pop();
+            marks.insert(id, Mark::Done);
+            None
+        }
+
+        let graph: HashMap<&str, &[String]> = self
+            .ingots
+            .iter()
+            .map(|i| (i.id.as_str(), i.deps.as_slice()))
+            .collect();
+        let mut marks = HashMap::new();
+        let mut stack = Vec::new();
+        for ingot in &self.ingots {
+            if let Some(cycle) = visit(&ingot.id, &graph, &mut marks, &mut stack) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    /// Point every `:deps` reference to `old_id` at `new_ids` instead.
+    /// Used when an ingot is rewritten, split, or regenerated under new ids.
+    pub fn redirect_deps(&mut self, old_id: &str, new_ids: &[String]) {
+        for ingot in &mut self.ingots {
+            if !ingot.deps.iter().any(|d| d == old_id) {
+                continue;
+            }
+            let mut deps: Vec<String> = Vec::new();
+            for dep in ingot.deps.drain(..) {
+                if dep == old_id {
+                    for new_id in new_ids {
+                        if *new_id != ingot.id && !deps.contains(new_id) {
+                            deps.push(new_id.clone());
+                        }
+                    }
+                } else if !deps.contains(&dep) {
+                    deps.push(dep);
+                }
+            }
+            ingot.deps = deps;
+        }
+    }
+
+    /// Get all solo ore ingots (can run in parallel)
+    pub fn solo_ore(&self) -> Vec<&Ingot> {
+        self.ingots
+            .iter()
+            .filter(|i| i.status == Status::Ore && i.solo)
+            .collect()
+    }
+
+    /// Get sequential ore ingots (solo=nil, no explicit deps)
+    pub fn sequential_ore(&self) -> Option<&Ingot> {
+        self.ingots
+            .iter()
+            .find(|i| i.status == Status::Ore && !i.solo && !i.has_deps())
+    }
+
+    /// Replace ingot(s) by id. If replacement is multiple ingots (split), all are inserted.
+    pub fn replace(&mut self, id: &str, replacements: Vec<Ingot>) {
+        if let Some(idx) = self.ingots.iter().position(|i| i.id == id) {
+            let new_ids: Vec<String> = replacements.iter().map(|i| i.id.clone()).collect();
+            self.ingots.remove(idx);
+            if !new_ids.iter().any(|new| new == id) {
+                self.removed.insert(id.to_string());
+            }
+            self.redirect_deps(id, &new_ids);
+            for (offset, ingot) in replacements.into_iter().enumerate() {
+                self.ingots.insert(idx + offset, ingot);
+            }
+        }
+    }
+
+    /// Fold an externally edited copy of PLAN.md into this state.
+    /// Slag owns the runtime fields (status, heat, smelt, session); everything else a
+    /// smith or user changed on disk is adopted, and ingots that only exist
+    /// on disk are appended, unless we replaced or split them away since the
+    /// last save. If the edited copy is unreadable, ours is kept.
+    pub fn merge_external(&mut self, content: &str) -> Result<(), SlagError> {
+        let external = Crucible::from_content(&self.path, content)?;
+
+        let mut merged: Vec<Ingot> = Vec::with_capacity(self.ingots.len());
+        for ours in &self.ingots {
+            match external.get(&ours.id) {
+                Some(theirs) => {
+                    let mut ingot = theirs.clone();
+                    ingot.status = ours.status.clone();
+                    ingot.heat = ours.heat;
+                    ingot.smelt = ours.smelt;
+                    ingot.set_extra_field(SESSION_FIELD, ours.extra_field(SESSION_FIELD));
+                    merged.push(ingot);
+                }
+                None => merged.push(ours.clone()),
+            }
+        }
+        for theirs in &external.ingots {
+            if self.get(&theirs.id).is_none() && !self.removed.contains(&theirs.id) {
+                merged.push(theirs.clone());
+            }
+        }
+
+        let candidate = Crucible {
+            path: self.path.clone(),
+            header_lines: external.header_lines,
+            ingots: merged,
+            removed: self.removed.clone(),
+        };
+        candidate.validate_deps()?;
+        *self = candidate;
+        Ok(())
+    }
+
+    /// Reset molten ingots left behind by a dead run back to ore.
+    /// Heat is kept so earlier attempts still count against `:max`.
+    /// Returns the ids that were reset.
+    pub fn reset_molten(&mut self) -> Vec<String> {
+        self.ingots
+            .iter_mut()
+            .filter(|i| i.status == Status::Molten)
+            .map(|i| {
+                i.status = Status::Ore;
+                i.id.clone()
+            })
+            .collect()
+    }
+
+    /// Count ingots by status
+    pub fn counts(&self) -> CrucibleCounts {
+        let mut counts = CrucibleCounts::default();
+        for ingot in &self.ingots {
+            match ingot.status {
+                Status::Ore => counts.ore += 1,
+                Status::Molten => counts.molten += 1,
+                Status::Forged => counts.forged += 1,
+                Status::Cracked => counts.cracked += 1,
+            }
+        }
+        counts.total = self.ingots.len();
+        counts
+    }
+
+    /// Check if any ore or molten ingots remain
+    pub fn has_pending(&self) -> bool {
+        self.ingots
+            .iter()
+            .any(|i| i.status == Status::Ore || i.status == Status::Molten)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CrucibleCounts {
+    pub total: usize,
+    pub ore: usize,
+    pub molten: usize,
+    pub forged: usize,
+    pub cracked: usize,
+}
+
+impl CrucibleCounts {
+    pub fn pct_forged(&self) -> u8 {
+        (self.forged * 100).checked_div(self.total).unwrap_or(0) as u8
+    }
+}
+
+/// Parse ingots from raw smith output (founder, re-smelt, regenerate).
+/// Prose around the ingots is ignored; malformed ingots are skipped with a
+/// warning that says where they are.
+pub fn parse_ingot_lines(raw: &str) -> Vec<Ingot> {
+    let scan = scan_crucible(raw);
+    for e in &scan.errors {
+        println!("    \x1b[31m⚠\x1b[0m skipped malformed ingot at {e}");
+    }
+    scan.ingots.into_iter().map(|s| s.ingot).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn sample_crucible() -> String {
+        r#";; CRUCIBLE 2026-01-27
+;; Blueprint: BLUEPRINT.md
+(ingot :id "i1" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "test -f file" :work "First task")
+(ingot :id "i2" :status forged :solo nil :grade 2 :heat 3 :max 5 :proof "npm test" :work "Second task")
+(ingot :id "i3" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "Third task")
+(ingot :id "i4" :status cracked :solo nil :grade 3 :heat 5 :max 5 :proof "curl -s url" :work "Fourth task")
+"#
+        .into()
+    }
+
+    fn write_temp(content: &str) -> NamedTempFile {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(content.as_bytes()).unwrap();
+        f
+    }
+
+    #[test]
+    fn load_crucible() {
+        let f = write_temp(&sample_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        assert_eq!(c.ingots.len(), 4);
+        assert_eq!(c.header_lines.len(), 2);
+    }
+
+    #[test]
+    fn counts() {
+        let f = write_temp(&sample_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        let counts = c.counts();
+        assert_eq!(counts.total, 4);
+        assert_eq!(counts.ore, 2);
+        assert_eq!(counts.forged, 1);
+        assert_eq!(counts.cracked, 1);
+        assert_eq!(counts.molten, 0);
+    }
+
+    #[test]
+    fn set_status_and_save() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.set_status("i1", Status::Molten);
+        c.save().unwrap();
+
+        let c2 = Crucible::load(f.path()).unwrap();
+        assert_eq!(c2.get("i1").unwrap().status, Status::Molten);
+    }
+
+    #[test]
+    fn increment_heat() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        assert_eq!(c.get("i1").unwrap().heat, 0);
+        c.increment_heat("i1");
+        assert_eq!(c.get("i1").unwrap().heat, 1);
+        c.increment_heat("i1");
+        assert_eq!(c.get("i1").unwrap().heat, 2);
+    }
+
+    #[test]
+    fn solo_ore() {
+        let f = write_temp(&sample_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        let solo = c.solo_ore();
+        assert_eq!(solo.len(), 2); // i1 and i3
+        assert!(solo.iter().all(|i| i.solo));
+    }
+
+    #[test]
+    fn sequential_ore() {
+        let f = write_temp(&sample_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        // No sequential ore in sample (i2 is forged, i4 is cracked)
+        assert!(c.sequential_ore().is_none());
+    }
+
+    #[test]
+    fn replace_single() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        let new_ingot = Ingot {
+            id: "i1".into(),
+            status: Status::Ore,
+            solo: true,
+            deps: vec![],
+            grade: 1,
+            skill: crate::sexp::Skill::Default,
+            heat: 0,
+            max: 5,
+            smelt: 1,
+            timeout: None,
+            fanout: None,
+            proof: "test -f newfile".into(),
+            work: "Rewritten task".into(),
+            extra: vec![],
+        };
+        c.replace("i1", vec![new_ingot]);
+        assert_eq!(c.ingots.len(), 4);
+        assert_eq!(c.get("i1").unwrap().work, "Rewritten task");
+        assert_eq!(c.get("i1").unwrap().smelt, 1);
+    }
+
+    #[test]
+    fn replace_split() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        let sub_a = Ingot {
+            id: "i1a".into(),
+            status: Status::Ore,
+            solo: true,
+            deps: vec![],
+            grade: 1,
+            skill: crate::sexp::Skill::Default,
+            heat: 0,
+            max: 5,
+            smelt: 1,
+            timeout: None,
+            fanout: None,
+            proof: "true".into(),
+            work: "Sub-task A".into(),
+            extra: vec![],
+        };
+        let sub_b = Ingot {
+            id: "i1b".into(),
+            status: Status::Ore,
+            solo: true,
+            deps: vec![],
+            grade: 1,
+            skill: crate::sexp::Skill::Default,
+            heat: 0,
+            max: 5,
+            smelt: 1,
+            timeout: None,
+            fanout: None,
+            proof: "true".into(),
+            work: "Sub-task B".into(),
+            extra: vec![],
+        };
+        c.replace("i1", vec![sub_a, sub_b]);
+        assert_eq!(c.ingots.len(), 5);
+        assert!(c.get("i1").is_none());
+        assert!(c.get("i1a").is_some());
+        assert!(c.get("i1b").is_some());
+    }
+
+    #[test]
+    fn has_pending() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        assert!(c.has_pending());
+
+        // Mark all ore as forged
+        c.set_status("i1", Status::Forged);
+        c.set_status("i3", Status::Forged);
+        assert!(!c.has_pending());
+    }
+
+    fn deps_crucible() -> String {
+        r#";; CRUCIBLE 2026-01-27
+(ingot :id "i1" :status forged :solo t :grade 1 :heat 1 :max 5 :proof "true" :work "Base")
+(ingot :id "i2" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "Other base")
+(ingot :id "i3" :status ore :solo nil :deps ("i1") :grade 1 :heat 0 :max 5 :proof "true" :work "Needs i1")
+(ingot :id "i4" :status ore :solo nil :deps ("i1" "i2") :grade 1 :heat 0 :max 5 :proof "true" :work "Needs i1, i2")
+"#
+        .into()
+    }
+
+    #[test]
+    fn ready_ore_respects_deps() {
+        let f = write_temp(&deps_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        let ready: Vec<&str> = c.ready_ore().iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ready, vec!["i2", "i3"]);
+    }
+
+    #[test]
+    fn ready_ore_unblocks_after_forge() {
+        let f = write_temp(&deps_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.set_status("i2", Status::Forged);
+        let ready: Vec<&str> = c.ready_ore().iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ready, vec!["i3", "i4"]);
+    }
+
+    #[test]
+    fn blocked_ore_on_cracked_dep() {
+        let f = write_temp(&deps_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.set_status("i2", Status::Cracked);
+        let blocked: Vec<&str> = c.blocked_ore().iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(blocked, vec!["i4"]);
+    }
+
+    #[test]
+    fn load_rejects_unknown_dep() {
+        let f = write_temp(
+            r#"(ingot :id "i1" :status ore :solo nil :deps ("i9") :grade 1 :heat 0 :max 5 :proof "true" :work "x")"#,
+        );
+        let err = Crucible::load(f.path()).unwrap_err().to_string();
+        assert!(err.contains("unknown ingot i9"), "{err}");
+    }
+
+    #[test]
+    fn load_rejects_cycle() {
+        let f = write_temp(
+            r#"(ingot :id "i1" :status ore :solo nil :deps ("i2") :grade 1 :heat 0 :max 5 :proof "true" :work "a")
+(ingot :id "i2" :status ore :solo nil :deps ("i1") :grade 1 :heat 0 :max 5 :proof "true" :work "b")
+"#,
+        );
+        let err = Crucible::load(f.path()).unwrap_err().to_string();
+        assert!(err.contains("cycle"), "{err}");
+    }
+
+    #[test]
+    fn split_redirects_deps() {
+        let f = write_temp(&deps_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        let mut sub_a = c.get("i1").unwrap().clone();
+        sub_a.id = "i1a".into();
+        let mut sub_b = sub_a.clone();
+        sub_b.id = "i1b".into();
+        c.replace("i1", vec![sub_a, sub_b]);
+        assert_eq!(c.get("i3").unwrap().deps, vec!["i1a", "i1b"]);
+        assert_eq!(c.get("i4").unwrap().deps, vec!["i1a", "i1b", "i2"]);
+        assert!(c.validate_deps().is_ok());
+    }
+
+    #[test]
+    fn merge_external_keeps_runtime_fields() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.set_status("i1", Status::Molten);
+        c.increment_heat("i1");
+        c.set_extra("i1", SESSION_FIELD, Some("s-1"));
+
+        // A smith rewrote i1's proof, reset its status, and added an ingot
+        let edited = sample_crucible()
+            .replace(r#":proof "test -f file""#, r#":proof "test -f other""#)
+            + r#"(ingot :id "i5" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "Added")"#;
+        c.merge_external(&edited).unwrap();
+
+        let i1 = c.get("i1").unwrap();
+        assert_eq!(i1.status, Status::Molten);
+        assert_eq!(i1.heat, 1);
+        assert_eq!(i1.proof, "test -f other");
+        assert_eq!(i1.extra_field(SESSION_FIELD), Some("s-1"));
+        assert!(c.get("i5").is_some());
+        assert_eq!(c.ingots.len(), 5);
+    }
+
+    #[test]
+    fn merge_external_skips_ingots_replaced_in_memory() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        let mut half = c.get("i2").unwrap().clone();
+        half.id = "i2a".into();
+        let mut other = half.clone();
+        other.id = "i2b".into();
+        c.replace("i2", vec![half, other]);
+
+        // PLAN.md was edited before the split reached it, so it still has i2
+        let edited = sample_crucible().replace("First task", "First task, edited");
+        c.merge_external(&edited).unwrap();
+
+        assert!(c.get("i2").is_none());
+        assert!(c.get("i2a").is_some() && c.get("i2b").is_some());
+
+        // Once written, an id that reappears on disk is a fresh ingot again
+        c.save().unwrap();
+        c.merge_external(&sample_crucible()).unwrap();
+        assert!(c.get("i2").is_some());
+    }
+
+    #[test]
+    fn save_keeps_comments_after_ingots() {
+        let content = sample_crucible().replace(
+            r#":work "Third task")"#,
+            r#":work "Third task") ; keep until v2"#,
+        );
+        let f = write_temp(&content);
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.save().unwrap();
+
+        let saved = std::fs::read_to_string(f.path()).unwrap();
+        assert!(saved.contains("; keep until v2"), "{saved}");
+        assert_eq!(Crucible::load(f.path()).unwrap().ingots.len(), 4);
+    }
+
+    #[test]
+    fn reset_molten_keeps_heat() {
+        let f = write_temp(&sample_crucible());
+        let mut c = Crucible::load(f.path()).unwrap();
+        c.set_status("i1", Status::Molten);
+        c.increment_heat("i1");
+
+        assert_eq!(c.reset_molten(), vec!["i1".to_string()]);
+        let i1 = c.get("i1").unwrap();
+        assert_eq!(i1.status, Status::Ore);
+        assert_eq!(i1.heat, 1);
+        assert!(c.reset_molten().is_empty());
+    }
+
+    #[test]
+    fn pct_forged() {
+        let f = write_temp(&sample_crucible());
+        let c = Crucible::load(f.path()).unwrap();
+        assert_eq!(c.counts().pct_forged(), 25); // 1 out of 4
+    }
+}
@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use super::Crucible;
+use crate::error::SlagError;
+use crate::sexp::{Ingot, Status};
+
+/// How long mutations may sit in memory before they are written together
+const FLUSH_DELAY: Duration = Duration::from_millis(100);
+
+/// Typed commands accepted by the crucible service
+#[derive(Debug)]
+pub enum Command {
+    IncrementHeat {
+        id: String,
+    },
+    SetStatus {
+        id: String,
+        status: Status,
+    },
+    /// Set or (with `None`) remove an extra field
+    SetExtra {
+        id: String,
+        key: String,
+        value: Option<String>,
+    },
+    /// Replace an ingot with its rewrite or split (see `Crucible::replace`)
+    Replace {
+        id: String,
+        ingots: Vec<Ingot>,
+    },
+    /// Reply with a copy of the current state, including unflushed changes
+    Snapshot(oneshot::Sender<Crucible>),
+    /// Write pending changes now
+    Flush(oneshot::Sender<Result<(), String>>),
+    /// Write pending changes and stop the service
+    Shutdown(oneshot::Sender<Result<(), String>>),
+}
+
+/// Cheap, cloneable handle for sending commands to the crucible service.
+/// Mutations are fire-and-forget; queries and flushes wait for a reply.
+#[derive(Clone)]
+pub struct CrucibleHandle {
+    tx: mpsc::UnboundedSender<Command>,
+}
+
+impl CrucibleHandle {
+    pub fn increment_heat(&self, id: &str) {
+        self.send(Command::IncrementHeat { id: id.to_string() });
+    }
+
+    pub fn set_status(&self, id: &str, status: Status) {
+        self.send(Command::SetStatus {
+            id: id.to_string(),
+            status,
+        });
+    }
+
+    pub fn set_extra(&self, id: &str, key: &str, value: Option<&str>) {
+        self.send(Command::SetExtra {
+            id: id.to_string(),
+            key: key.to_string(),
+            value: value.map(String::from),
+        });
+    }
+
+    pub fn replace(&self, id: &str, ingots: Vec<Ingot>) {
+        self.send(Command::Replace {
+            id: id.to_string(),
+            ingots,
+        });
+    }
+
+    /// Get a copy of the authoritative crucible state
+    pub async fn snapshot(&self) -> Result<Crucible, SlagError> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Snapshot(tx));
+        rx.await.map_err(|_| stopped())
+    }
+
+    /// Write pending changes to PLAN.md
+    pub async fn flush(&self) -> Result<(), SlagError> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Flush(tx));
+        rx.await
+            .map_err(|_| stopped())?
+            .map_err(SlagError::CrucibleParse)
+    }
+
+    /// Flush and stop the service
+    pub async fn shutdown(&self) -> Result<(), SlagError> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Shutdown(tx));
+        rx.await
+            .map_err(|_| stopped())?
+            .map_err(SlagError::CrucibleParse)
+    }
+
+    fn send(&self, cmd: Command) {
+        // A stopped service surfaces on the next snapshot/flush
+        let _ = self.tx.send(cmd);
+    }
+}
+
+fn stopped() -> SlagError {
+    SlagError::CrucibleParse("crucible service stopped".into())
+}
+
+/// Single writer for PLAN.md while the forge runs.
+/// Holds the authoritative state, applies commands in arrival order and
+/// coalesces writes. If PLAN.md changed on disk since our last write
+/// (a smith edited it), the edit is merged in before writing.
+pub struct CrucibleService {
+    crucible: Crucible,
+    /// Content we last wrote (or loaded), to detect external edits
+    last_written: Option<String>,
+    /// When set, pending changes must be written by this deadline
+    flush_at: Option<Instant>,
+}
+
+impl CrucibleService {
+    /// Start the service on the current tokio runtime
+    pub fn spawn(crucible: Crucible) -> CrucibleHandle {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let last_written = std::fs::read_to_string(&crucible.path).ok();
+        let service = CrucibleService {
+            crucible,
+            last_written,
+            flush_at: None,
+        };
+        tokio::spawn(service.run(rx));
+        CrucibleHandle { tx }
+    }
+
+    async fn run(mut self, mut rx: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            let cmd = match self.flush_at {
+                Some(deadline) => match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(cmd) => cmd,
+                    Err(_) => {
+                        self.flush_logged();
+                        continue;
+                    }
+                },
+                None => rx.recv().await,
+            };
+
+            // Every handle dropped: write what we have and stop
+            let Some(cmd) = cmd else {
+                self.flush_logged();
+                return;
+            };
+
+            match cmd {
+                Command::IncrementHeat { id } => {
+                    self.crucible.increment_heat(&id);
+                    self.mark_dirty();
+                }
+                Command::SetStatus { id, status } => {
+                    self.crucible.set_status(&id, status);
+                    self.mark_dirty();
+                }
+                Command::SetExtra { id, key, value } => {
+                    self.crucible.set_extra(&id, &key, value.as_deref());
+                    self.mark_dirty();
+                }
+                Command::Replace { id, ingots } => {
+                    self.crucible.replace(&id, ingots);
+                    self.mark_dirty();
+                }
+                Command::Snapshot(reply) => {
+                    let _ = reply.send(self.crucible.clone());
+                }
+                Command::Flush(reply) => {
+                    let _ = reply.send(self.flush().map_err(|e| e.to_string()));
+                }
+                Command::Shutdown(reply) => {
+                    let _ = reply.send(self.flush().map_err(|e| e.to_string()));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self) {
+        if self.flush_at.is_none() {
+            self.flush_at = Some(Instant::now() + FLUSH_DELAY);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SlagError> {
+        if self.flush_at.take().is_none() {
+            return Ok(());
+        }
+
+        let on_disk = std::fs::read_to_string(&self.crucible.path).ok();
+        if let Some(content) = on_disk {
+            if self.last_written.as_deref() != Some(content.as_str()) {
+                match self.crucible.merge_external(&content) {
+                    Ok(()) => println!("    \x1b[90m↳ merged external edits to PLAN.md\x1b[0m"),
+                    Err(e) => {
+                        eprintln!("    \x1b[31m⚠\x1b[0m ignoring unreadable PLAN.md edit: {e}")
+                    }
+                }
+            }
+        }
+
+        self.crucible.save()?;
+        self.last_written = Some(self.crucible.build_content());
+        Ok(())
+    }
+
+    fn flush_logged(&mut self) {
+        if let Err(e) = self.flush() {
+            eprintln!("  \x1b[31m✗\x1b[0m crucible write failed: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const PLAN: &str = r#";; CRUCIBLE 2026-01-27
+(ingot :id "i1" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "First")
+(ingot :id "i2" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "Second")
+"#;
+
+    fn write_temp(content: &str) -> NamedTempFile {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(content.as_bytes()).unwrap();
+        f
+    }
+
+    #[tokio::test]
+    async fn concurrent_heats_are_not_lost() {
+        let f = write_temp(PLAN);
+        let handle = CrucibleService::spawn(Crucible::load(f.path()).unwrap());
+
+        let mut tasks = Vec::new();
+        for _ in 0..10 {
+            let h = handle.clone();
+            tasks.push(tokio::spawn(async move { h.increment_heat("i1") }));
+        }
+        for t in tasks {
+            t.await.unwrap();
+        }
+        handle.shutdown().await.unwrap();
+
+        let c = Crucible::load(f.path()).unwrap();
+        assert_eq!(c.get("i1").unwrap().heat, 10);
+    }
+
+    #[tokio::test]
+    async fn snapshot_sees_unflushed_changes() {
+        let f = write_temp(PLAN);
+        let handle = CrucibleService::spawn(Crucible::load(f.path()).unwrap());
+        handle.set_status("i2", Status::Molten);
+
+        let snap = handle.snapshot().await.unwrap();
+        assert_eq!(snap.get("i2").unwrap().status, Status::Molten);
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn flush_merges_external_edit() {
+        let f = write_temp(PLAN);
+        let handle = CrucibleService::spawn(Crucible::load(f.path()).unwrap());
+        handle.set_status("i1", Status::Forged);
+        handle.flush().await.unwrap();
+
+        // A smith edits PLAN.md behind the service's back
+        let edited = std::fs::read_to_string(f.path())
+            .unwrap()
+            .replace(r#":work "Second""#, r#":work "Second, clarified""#);
+        std::fs::write(f.path(), edited).unwrap();
+
+        handle.increment_heat("i2");
+        handle.shutdown().await.unwrap();
+
+        let c = Crucible::load(f.path()).unwrap();
+        assert_eq!(c.get("i1").unwrap().status, Status::Forged);
+        assert_eq!(c.get("i2").unwrap().heat, 1);
+        assert_eq!(c.get("i2").unwrap().work, "Second, clarified");
+    }
+}
@@ -14,6 +14,12 @@ pub enum SlagError {
     #[error("smith invocation failed: {0}")]
     SmithFailed(String),
 
+    #[error("smith backend unavailable: {0}")]
+    SmithTransient(String),
+
+    #[error("smith timed out after {0}s")]
+    SmithTimeout(u64),
+
     #[error("no ingots produced by founder")]
     NoIngots,
 
@@ -23,12 +29,30 @@ pub enum SlagError {
     #[error("ingot {0} cracked after {1} heats")]
     IngotCracked(String, u8),
 
+    #[error("lint failed: {0} errors in crucible")]
+    LintFailed(usize),
+
     #[error("forge failed: {0} ingots cracked")]
     ForgeFailed(usize),
 
     #[error("proof failed for {id}: {reason}")]
     ProofFailed { id: String, reason: String },
 
+    #[error("bad file block: {0}")]
+    BadFileBlock(String),
+
+    #[error("budget spent (${spent:.2} of ${limit:.2}) — raise it and run `slag resume`")]
+    BudgetSpent { spent: f64, limit: f64 },
+
+    #[error("interrupted — run `slag resume` to continue")]
+    Interrupted,
+
+    #[error("slag is already running here (pid {pid}, run {run_id})")]
+    RunLocked { pid: u32, run_id: String },
+
+    #[error("config error: {0}")]
+    Config(String),
+
     #[error("self-update failed: {0}")]
     UpdateFailed(String),
 
@@ -1,16 +1,19 @@
-use crate::config::{ALLOY_FILE, BLUEPRINT, CRUCIBLE, HIGH_GRADE, LEDGER};
+use crate::config::{paths, project_path, settings};
 use crate::sexp::Ingot;
 
 /// Build the prompt (flux) for striking an ingot.
 /// Includes blueprint, alloy recipes, crucible state, ledger, git diff.
 pub fn prepare_flux(ingot: &Ingot, slag: Option<&str>) -> String {
-    let blueprint = std::fs::read_to_string(BLUEPRINT).unwrap_or_else(|_| "None".into());
-    let alloy = std::fs::read_to_string(ALLOY_FILE).unwrap_or_else(|_| "None yet".into());
-    let crucible = std::fs::read_to_string(CRUCIBLE).unwrap_or_else(|_| "Empty".into());
-    let ledger = read_tail(LEDGER, 25);
+    let blueprint =
+        std::fs::read_to_string(project_path(&paths().blueprint)).unwrap_or_else(|_| "None".into());
+    let alloy =
+        std::fs::read_to_string(project_path(&paths().alloy)).unwrap_or_else(|_| "None yet".into());
+    let crucible =
+        std::fs::read_to_string(project_path(&paths().crucible)).unwrap_or_else(|_| "Empty".into());
+    let ledger = read_tail(&project_path(&paths().ledger), 25);
     let git_diff = git_diff_stat();
 
-    let complex_note = if ingot.grade >= HIGH_GRADE {
+    let complex_note = if ingot.grade >= settings().thresholds.high_grade {
         " ◉ COMPLEX"
     } else {
         ""
@@ -60,8 +63,13 @@ pub fn prepare_flux(ingot: &Ingot, slag: Option<&str>) -> String {
         flux.push_str("=== INSTRUCTIONS ===\n");
         flux.push_str("1. Forge this ingot completely\n");
         flux.push_str("2. Create/modify all necessary files\n");
-        flux.push_str("3. Add useful patterns to AGENTS.md\n");
-        flux.push_str("4. End with exactly: CMD: <shell command to verify>\n\n");
+        flux.push_str(&format!(
+            "3. Add useful patterns to {}\n",
+            project_path(&paths().alloy).display()
+        ));
+        flux.push_str("4. End with this report block:\n");
+        flux.push_str(REPORT_FORMAT);
+        flux.push('\n');
 
         if ingot.is_complex() {
             flux.push_str("◉ COMPLEX - think through edge cases\n");
@@ -73,18 +81,74 @@ pub fn prepare_flux(ingot: &Ingot, slag: Option<&str>) -> String {
         flux.push_str(
             "\nRULES:\n\
             - NO QUESTIONS. You are the expert.\n\
-            - NO PROSE. Just code and CMD.\n\
-            - The CMD must pass for the ingot to be forged.\n",
+            - NO PROSE. Just code and the report.\n\
+            - The report's cmd must pass for the ingot to be forged.\n",
         );
     }
 
     flux
 }
 
+/// Flux for a later heat in the smith's own session: it already has the
+/// forge order and blueprint, so only what went wrong is sent
+pub fn prepare_session_flux(ingot: &Ingot, heat: u8, slag: Option<&str>) -> String {
+    let slag_msg = slag.unwrap_or("The previous attempt ended before the proof was run.");
+    format!(
+        "=== HEAT {heat}/{max} [{id}] ===\n\
+        !!! CRACKED - PREVIOUS ATTEMPT FAILED !!!\n\
+        {slag_msg}\n\
+        !!! ANALYZE AND FIX !!!\n\
+        \n\
+        Same forge order as before. The proof must pass: {proof}\n\
+        End with the report block as before:\n\
+        {REPORT_FORMAT}",
+        max = ingot.max,
+        id = ingot.id,
+        proof = ingot.proof,
+    )
+}
+
+/// The block a strike ends with, read by `proof::extract_report`
+pub const REPORT_FORMAT: &str = "```json\n\
+    {\"cmd\": \"<shell command to verify>\",\n \
+    \"files_touched\": [\"<paths you created or changed>\"],\n \
+    \"learnings\": \"<anything later ingots should know, or null>\",\n \
+    \"confidence\": <0.0-1.0, how sure you are the cmd passes>}\n\
+    ```\n";
+
+/// Slag for a heat the smith did not finish in time
+pub fn timeout_slag(secs: u64) -> String {
+    format!(
+        "TIMEOUT: the smith was stopped after {secs}s before it finished.\n\
+        Too slow, not wrong: take the shortest path, skip long exploration and \
+        slow commands, and reach the report sooner."
+    )
+}
+
+/// Slag for a heat lost to a backend outage that outlasted the retries
+pub fn transient_slag(detail: &str) -> String {
+    format!(
+        "BACKEND UNAVAILABLE: the last attempt never reached a result ({detail}).\n\
+        Your previous approach was not judged; pick up where it left off."
+    )
+}
+
+/// Appended to the flux for smiths that cannot edit files themselves;
+/// slag applies the blocks before running the CMD.
+pub const FILE_BLOCK_PROTOCOL: &str = "\n=== FILE EDITS ===\n\
+    You cannot edit files directly. For every file you create or change,\n\
+    output its COMPLETE new contents in a block:\n\
+    === FILE: relative/path/from/project/root ===\n\
+    <full file contents>\n\
+    === END FILE ===\n\
+    Files without a block are left as they are. End with the report as usual.\n";
+
 /// Build the re-smelt analysis prompt for a cracked ingot
 pub fn prepare_resmelt_flux(ingot: &Ingot, failure_logs: &str) -> String {
-    let blueprint = std::fs::read_to_string(BLUEPRINT).unwrap_or_else(|_| "None".into());
-    let crucible = std::fs::read_to_string(CRUCIBLE).unwrap_or_else(|_| "Empty".into());
+    let blueprint =
+        std::fs::read_to_string(project_path(&paths().blueprint)).unwrap_or_else(|_| "None".into());
+    let crucible =
+        std::fs::read_to_string(project_path(&paths().crucible)).unwrap_or_else(|_| "Empty".into());
     let git_state = git_log_and_diff();
 
     format!(
@@ -130,8 +194,10 @@ pub fn prepare_resmelt_flux(ingot: &Ingot, failure_logs: &str) -> String {
 /// Build the reconsider prompt — surveyor-scoped re-analysis of a twice-failed ingot.
 /// Unlike re-smelt (which tweaks proof/work), this questions the fundamental approach.
 pub fn prepare_reconsider_flux(ingot: &Ingot, failure_logs: &str) -> String {
-    let blueprint = std::fs::read_to_string(BLUEPRINT).unwrap_or_else(|_| "None".into());
-    let crucible = std::fs::read_to_string(CRUCIBLE).unwrap_or_else(|_| "Empty".into());
+    let blueprint =
+        std::fs::read_to_string(project_path(&paths().blueprint)).unwrap_or_else(|_| "None".into());
+    let crucible =
+        std::fs::read_to_string(project_path(&paths().crucible)).unwrap_or_else(|_| "Empty".into());
     let git_state = git_log_and_diff();
 
     format!(
@@ -215,6 +281,7 @@ pub fn founder_prompt(ore: &str, blueprint: &str) -> String {
         - :id = unique (i1, i2, ...)\n\
         - :status = ore (always)\n\
         - :solo = t (parallel ok, no deps) | nil (sequential, has deps)\n\
+        - :deps = (\"i1\" \"i3\") ids that must be forged first (omit if none)\n\
         - :grade = 1-5 complexity (3+ gets plan mode)\n\
         - :skill = web|api|cli|default (selects tools/plugins)\n\
         - :heat = 0\n\
@@ -228,7 +295,7 @@ pub fn founder_prompt(ore: &str, blueprint: &str) -> String {
         - npm test / npx playwright test\n\
         - curl -s URL | grep -q PATTERN\n\n\
         RULES:\n\
-        - Follow blueprint dependency graph\n\
+        - Follow blueprint dependency graph; encode its edges as :deps\n\
         - :solo t for independent tasks (can parallel)\n\
         - :solo nil for dependent tasks (sequential)\n\
         - Prefer grade 1-2, split complex work\n\
@@ -240,8 +307,10 @@ pub fn founder_prompt(ore: &str, blueprint: &str) -> String {
 
 /// Build the regenerate prompt for failed ingots
 pub fn regenerate_prompt(cracked_descriptions: &str) -> String {
-    let blueprint = std::fs::read_to_string(BLUEPRINT).unwrap_or_else(|_| "None".into());
-    let crucible = std::fs::read_to_string(CRUCIBLE).unwrap_or_else(|_| "Empty".into());
+    let blueprint =
+        std::fs::read_to_string(project_path(&paths().blueprint)).unwrap_or_else(|_| "None".into());
+    let crucible =
+        std::fs::read_to_string(project_path(&paths().crucible)).unwrap_or_else(|_| "Empty".into());
 
     format!(
         "=== REGENERATE CRACKED INGOTS ===\n\
@@ -274,6 +343,32 @@ pub fn regenerate_prompt(cracked_descriptions: &str) -> String {
     )
 }
 
+/// Ask the reviewer to choose among fanout candidates that all passed the
+/// proof; `candidates` pairs each candidate's number with its diff
+pub fn prepare_pick_flux(ingot: &Ingot, candidates: &[(usize, String)]) -> String {
+    let mut flux = format!(
+        "=== PICK A CANDIDATE ===\n\
+        Several smiths forged the same ingot in parallel and every one passed its proof.\n\
+        Keep the best; the others are discarded.\n\n\
+        INGOT: [{id}] {work}\n\
+        PROOF: {proof}\n\n",
+        id = ingot.id,
+        work = ingot.work,
+        proof = ingot.proof,
+    );
+    for (n, diff) in candidates {
+        flux.push_str(&format!("=== CANDIDATE {n} ===\n{diff}\n\n"));
+    }
+    flux.push_str(
+        "=== YOUR TASK ===\n\
+        Prefer the candidate that is correct, simplest and fits the codebase best.\n\n\
+        OUTPUT FORMAT (exactly this):\n\
+        PICK: <candidate number>\n\
+        REASON: <one sentence>\n",
+    );
+    flux
+}
+
 /// Build the master review prompt for the review phase
 pub fn prepare_review_flux(
     ingot_id: &str,
@@ -359,7 +454,7 @@ pub fn prepare_review_flux(
     )
 }
 
-fn read_tail(path: &str, lines: usize) -> String {
+fn read_tail(path: &std::path::Path, lines: usize) -> String {
     match std::fs::read_to_string(path) {
         Ok(content) => {
             let all_lines: Vec<&str> = content.lines().collect();
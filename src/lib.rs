@@ -0,0 +1,23 @@
+//! slag: smelt ideas, skim the bugs, forge the product.
+//!
+//! The `slag` binary is a thin CLI over this crate; integration tests drive
+//! the pipeline through it with their own smiths.
+
+#![allow(dead_code)]
+
+pub mod anvil;
+pub mod cli;
+pub mod config;
+pub mod crucible;
+pub mod error;
+pub mod flux;
+pub mod lint;
+pub mod pipeline;
+pub mod progress;
+pub mod proof;
+pub mod sandbox;
+pub mod sexp;
+pub mod shutdown;
+pub mod smith;
+pub mod tui;
+pub mod update;
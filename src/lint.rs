@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::crucible::Crucible;
+use crate::error::SlagError;
+use crate::sexp::parser::{scan_crucible, ScannedIngot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One lint finding, anchored to a line (and column when known)
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub col: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LintReport {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+
+    fn error(&mut self, line: usize, col: Option<usize>, message: String) {
+        self.push(Severity::Error, line, col, message);
+    }
+
+    fn warning(&mut self, line: usize, message: String) {
+        self.push(Severity::Warning, line, None, message);
+    }
+
+    fn push(&mut self, severity: Severity, line: usize, col: Option<usize>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line,
+            col,
+            message,
+        });
+    }
+}
+
+/// `slag lint`: check a crucible file and print the findings.
+/// Fails if any errors were found.
+pub async fn run(path: &Path) -> Result<(), SlagError> {
+    let report = lint_file(path).await?;
+    print_report(path, &report);
+    if report.errors() > 0 {
+        return Err(SlagError::LintFailed(report.errors()));
+    }
+    Ok(())
+}
+
+/// Lint a crucible file on disk
+pub async fn lint_file(path: &Path) -> Result<LintReport, SlagError> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(lint_content(&content).await)
+}
+
+/// Lint crucible content: parse errors, duplicate ids, out-of-range fields,
+/// broken `:deps`, and `:proof` commands that are not valid bash.
+pub async fn lint_content(content: &str) -> LintReport {
+    let mut report = LintReport::default();
+    let scan = scan_crucible(content);
+
+    for e in &scan.errors {
+        report.error(e.line, Some(e.col), e.message.clone());
+    }
+    for w in &scan.warnings {
+        report.warning(w.line, w.message.clone());
+    }
+
+    let lines: HashMap<&str, usize> = scan
+        .ingots
+        .iter()
+        .rev()
+        .map(|s| (s.ingot.id.as_str(), s.line))
+        .collect();
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for scanned in &scan.ingots {
+        let ScannedIngot { ingot, line } = scanned;
+        let line = *line;
+
+        if let Some(first) = seen.insert(&ingot.id, line) {
+            report.error(
+                line,
+                None,
+                format!("duplicate id {} (first defined on line {first})", ingot.id),
+            );
+        }
+        if !(1..=5).contains(&ingot.grade) {
+            report.error(
+                line,
+                None,
+                format!("[{}] :grade {} is outside 1-5", ingot.id, ingot.grade),
+            );
+        }
+        if ingot.max == 0 {
+            report.error(
+                line,
+                None,
+                format!("[{}] :max 0 leaves no heats to forge with", ingot.id),
+            );
+        } else if ingot.heat > ingot.max {
+            report.warning(
+                line,
+                format!(
+                    "[{}] :heat {} exceeds :max {}",
+                    ingot.id, ingot.heat, ingot.max
+                ),
+            );
+        }
+        if ingot.timeout == Some(0) {
+            report.error(
+                line,
+                None,
+                format!("[{}] :timeout 0 gives the smith no time", ingot.id),
+            );
+        }
+        if ingot.fanout == Some(0) {
+            report.error(
+                line,
+                None,
+                format!("[{}] :fanout 0 strikes no candidates", ingot.id),
+            );
+        }
+        for dep in &ingot.deps {
+            if dep == &ingot.id {
+                report.error(line, None, format!("[{}] depends on itself", ingot.id));
+            } else if !lines.contains_key(dep.as_str()) {
+                report.error(
+                    line,
+                    None,
+                    format!("[{}] depends on unknown ingot {dep}", ingot.id),
+                );
+            }
+        }
+        if ingot.work.trim().is_empty() {
+            report.warning(line, format!("[{}] has no :work description", ingot.id));
+        }
+
+        let proof = ingot.proof.trim();
+        if proof.is_empty() || proof == "true" {
+            report.warning(
+                line,
+                format!("[{}] :proof always passes; nothing is verified", ingot.id),
+            );
+        } else if let Err(msg) = check_bash_syntax(proof).await {
+            report.error(
+                line,
+                None,
+                format!("[{}] :proof is not valid bash: {msg}", ingot.id),
+            );
+        }
+    }
+
+    // Unknown deps were reported above; find_cycle only follows known ids
+    let ingots: Vec<_> = scan.ingots.iter().map(|s| s.ingot.clone()).collect();
+    let crucible = Crucible::new(Path::new("PLAN.md"), ingots);
+    if let Some(cycle) = crucible.find_cycle() {
+        if cycle.len() > 2 {
+            let line = lines.get(cycle[0].as_str()).copied().unwrap_or(1);
+            report.error(
+                line,
+                None,
+                format!("dependency cycle: {}", cycle.join(" -> ")),
+            );
+        }
+    }
+
+    report
+        .diagnostics
+        .sort_by_key(|d| (d.line, d.col.unwrap_or(0)));
+    report
+}
+
+/// Run `bash -n` on a command; returns bash's complaint if it does not parse
+async fn check_bash_syntax(cmd: &str) -> Result<(), String> {
+    match tokio::process::Command::new("bash")
+        .args(["-n", "-c", cmd])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let msg = stderr
+                .lines()
+                .next()
+                .unwrap_or("syntax error")
+                .trim_start_matches("bash: -c: ")
+                .to_string();
+            Err(msg)
+        }
+        // No bash to ask: nothing we can say about the proof
+        Err(_) => Ok(()),
+    }
+}
+
+/// Print diagnostics as `file:line:col severity: message`
+pub fn print_report(path: &Path, report: &LintReport) {
+    let name = path.display();
+    println!();
+    for d in &report.diagnostics {
+        let location = match d.col {
+            Some(col) => format!("{name}:{}:{col}", d.line),
+            None => format!("{name}:{}", d.line),
+        };
+        let label = match d.severity {
+            Severity::Error => "\x1b[31merror\x1b[0m",
+            Severity::Warning => "\x1b[38;5;220mwarning\x1b[0m",
+        };
+        println!("  \x1b[90m{location}\x1b[0m {label}: {}", d.message);
+    }
+
+    if report.diagnostics.is_empty() {
+        println!("  \x1b[1;37m█\x1b[0m {name} is clean");
+    } else {
+        println!(
+            "\n  {} {} errors, {} warnings",
+            if report.errors() > 0 {
+                "\x1b[31m✗\x1b[0m"
+            } else {
+                "\x1b[38;5;220m◐\x1b[0m"
+            },
+            report.errors(),
+            report.warnings(),
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(report: &LintReport) -> Vec<String> {
+        report
+            .diagnostics
+            .iter()
+            .map(|d| format!("{}:{}", d.line, d.message))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn clean_plan_has_no_errors() {
+        let content = r#";; CRUCIBLE
+(ingot :id "i1" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "test -f a" :work "A")
+(ingot :id "i2" :status ore :solo nil :deps ("i1") :grade 2 :heat 0 :max 5 :proof "grep -q \"x\" a" :work "B")
+"#;
+        let report = lint_content(content).await;
+        assert_eq!(report.errors(), 0, "{:?}", messages(&report));
+        assert_eq!(report.warnings(), 0);
+    }
+
+    #[tokio::test]
+    async fn reports_field_problems_with_lines() {
+        let content = r#";; CRUCIBLE
+(ingot :id "i1" :grade 7 :max 5 :proof "test -f a" :work "A")
+(ingot :id "i1" :grade 1 :max 0 :proof "test -f b" :work "B")
+(ingot :id "i3" :status hot :work "C")
+"#;
+        let report = lint_content(content).await;
+        let msgs = messages(&report);
+        assert!(msgs
+            .iter()
+            .any(|m| m.starts_with("2:") && m.contains("outside 1-5")));
+        assert!(msgs
+            .iter()
+            .any(|m| m.starts_with("3:") && m.contains("duplicate id i1")));
+        assert!(msgs
+            .iter()
+            .any(|m| m.starts_with("3:") && m.contains(":max 0")));
+        assert!(msgs
+            .iter()
+            .any(|m| m.starts_with("4:") && m.contains("unknown status 'hot'")));
+        assert_eq!(report.errors(), 4);
+    }
+
+    #[tokio::test]
+    async fn reports_invalid_bash_proof() {
+        let content = r#"(ingot :id "i1" :proof "if [ -f a ]; then echo" :work "A")"#;
+        let report = lint_content(content).await;
+        assert_eq!(report.errors(), 1, "{:?}", messages(&report));
+        assert!(report.diagnostics[0].message.contains("not valid bash"));
+    }
+
+    #[tokio::test]
+    async fn reports_bad_deps() {
+        let content = r#"(ingot :id "i1" :deps ("i2") :proof "test -f a" :work "A")
+(ingot :id "i2" :deps ("i1") :proof "test -f b" :work "B")
+(ingot :id "i3" :deps ("i9") :proof "test -f c" :work "C")
+"#;
+        let report = lint_content(content).await;
+        let msgs = messages(&report);
+        assert!(msgs.iter().any(|m| m.contains("unknown ingot i9")));
+        assert!(msgs
+            .iter()
+            .any(|m| m.contains("dependency cycle: i1 -> i2 -> i1")));
+    }
+
+    #[tokio::test]
+    async fn unknown_skill_is_a_warning() {
+        let content = r#"(ingot :id "i1" :skill docker :proof "test -f a" :work "A")"#;
+        let report = lint_content(content).await;
+        assert_eq!(report.errors(), 0, "{:?}", messages(&report));
+        assert_eq!(
+            messages(&report),
+            ["1:unknown skill 'docker', forged as default"]
+        );
+    }
+
+    #[tokio::test]
+    async fn trivial_proof_is_a_warning() {
+        let content = r#"(ingot :id "i1" :proof "true" :work "A")"#;
+        let report = lint_content(content).await;
+        assert_eq!(report.errors(), 0);
+        assert_eq!(report.warnings(), 1);
+    }
+}
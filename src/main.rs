@@ -1,76 +1,116 @@
-#![allow(dead_code)]
-
-mod anvil;
-mod cli;
-mod config;
-mod crucible;
-mod error;
-mod flux;
-mod pipeline;
-mod progress;
-mod proof;
-mod sexp;
-mod smith;
-mod tui;
-mod update;
-
 use std::path::Path;
 
 use clap::Parser;
 
-use cli::{Cli, Command};
-use config::{PipelineConfig, SmithConfig};
+use slag::cli::{Cli, Command};
+use slag::config::{self, paths, project_path, Settings, SmithConfig};
+use slag::{crucible, error, lint, pipeline, sexp, shutdown, smith, tui, update};
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    // Ensure logs directory exists
-    let _ = std::fs::create_dir_all(config::LOG_DIR);
-
-    let pipeline_config = PipelineConfig::new(
-        cli.worktree,
-        cli.anvils,
-        cli.skip_review,
-        cli.keep_branches,
-        cli.ci_only,
-        cli.review_all,
-        cli.retry,
-    );
-
-    let result = match cli.command {
-        Some(Command::Status) => show_status(),
-        Some(Command::Update) => update::self_update().await,
-        Some(Command::Resume) => {
-            let smith_config = SmithConfig::from_env();
-            pipeline::run(None, &smith_config, &pipeline_config).await
+    // Config files and env first, CLI flags on top
+    let mut settings = match Settings::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("\n  \x1b[31m✗\x1b[0m {e}\n");
+            std::process::exit(1);
         }
-        None => {
-            let smith_config = SmithConfig::from_env();
-            let commission = cli.commission_text();
-            pipeline::run(commission.as_deref(), &smith_config, &pipeline_config).await
+    };
+    cli.apply(&mut settings);
+    config::init(settings);
+
+    // Older forges kept their files in the project root
+    match config::migrate_root_layout(Path::new("."), paths()) {
+        Ok(moved) if !moved.is_empty() => println!(
+            "  \x1b[90m↳ moved {} into {}/\x1b[0m",
+            moved.join(", "),
+            paths().state_dir
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("  \x1b[31m⚠\x1b[0m could not migrate slag files: {e}"),
+    }
+
+    // Ensure logs directory exists
+    let _ = std::fs::create_dir_all(project_path(&paths().log_dir));
+
+    let pipeline_config = config::settings().pipeline.clone();
+
+    // Ctrl-C: the forge stops gracefully; anywhere else the command is dropped
+    shutdown::install();
+    let command = async {
+        match cli.command {
+            Some(Command::Status) => show_status(),
+            Some(Command::Update) => update::self_update().await,
+            Some(Command::Lint { file }) => {
+                let file = file.unwrap_or_else(|| project_path(&paths().crucible));
+                lint::run(&file).await
+            }
+            Some(Command::Export { format }) => {
+                crucible::export::export(&project_path(&paths().crucible), format)
+            }
+            Some(Command::Import {
+                file,
+                format,
+                force,
+            }) => {
+                crucible::export::import(&file, format, &project_path(&paths().crucible), force)
+                    .await
+            }
+            Some(Command::Cassette { logs, out }) => {
+                let logs = logs.unwrap_or_else(|| project_path(&paths().log_dir));
+                let cassette = smith::cassette::Cassette::from_logs(&logs)?;
+                cassette.save(&out)?;
+                println!(
+                    "  \x1b[90m↳ {} smith calls from {} into {}\x1b[0m",
+                    cassette.takes().len(),
+                    logs.display(),
+                    out.display()
+                );
+                Ok(())
+            }
+            Some(Command::Resume) => {
+                let smith_config = SmithConfig::from_settings(config::settings())?;
+                pipeline::run(None, &smith_config, &pipeline_config).await
+            }
+            None => {
+                let smith_config = SmithConfig::from_settings(config::settings())?;
+                let commission = cli.commission_text();
+                pipeline::run(commission.as_deref(), &smith_config, &pipeline_config).await
+            }
         }
     };
+    let result = tokio::select! {
+        result = command => result,
+        _ = shutdown::abandoned() => Err(error::SlagError::Interrupted),
+    };
 
     if let Err(e) = result {
+        shutdown::kill_stragglers();
         eprintln!("\n  \x1b[31m✗\x1b[0m {e}\n");
-        std::process::exit(1);
+        let code = if matches!(e, error::SlagError::Interrupted) {
+            130
+        } else {
+            1
+        };
+        std::process::exit(code);
     }
 }
 
 fn show_status() -> Result<(), error::SlagError> {
     tui::show_banner();
 
-    let crucible_path = Path::new(config::CRUCIBLE);
+    let crucible_path = project_path(&paths().crucible);
     if !crucible_path.exists() {
         println!("\n  No crucible found. Run `slag \"Your Commission\"` to start.\n");
         return Ok(());
     }
 
-    let crucible = crucible::Crucible::load(crucible_path)?;
+    let crucible = crucible::Crucible::load(&crucible_path)?;
     let counts = crucible.counts();
 
-    let ore_path = Path::new(config::ORE_FILE);
+    let ore_path = project_path(&paths().ore);
     if ore_path.exists() {
         let ore = std::fs::read_to_string(ore_path)?;
         let commission = ore.lines().last().unwrap_or("(unknown)");
@@ -80,7 +120,7 @@ fn show_status() -> Result<(), error::SlagError> {
         );
     }
 
-    let has_bp = Path::new(config::BLUEPRINT).exists();
+    let has_bp = project_path(&paths().blueprint).exists();
     println!(
         "  \x1b[90mBlueprint: {}\x1b[0m",
         if has_bp { "yes" } else { "no" }
@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::config::{SmithConfig, CRUCIBLE};
+use crate::config::{paths, project_path};
 use crate::crucible::Crucible;
 use crate::error::SlagError;
 use crate::flux;
@@ -43,13 +41,9 @@ pub enum AnalysisAction {
 }
 
 /// Analyze cracked ingots and prepare for retry
-pub async fn analyze_and_prepare(
-    smith: &dyn Smith,
-    _config: &SmithConfig,
-    cycle: usize,
-) -> Result<bool, SlagError> {
-    let crucible_path = Path::new(CRUCIBLE);
-    let mut crucible = Crucible::load(crucible_path)?;
+pub async fn analyze_and_prepare(smith: &dyn Smith, cycle: usize) -> Result<bool, SlagError> {
+    let crucible_path = project_path(&paths().crucible);
+    let mut crucible = Crucible::load(&crucible_path)?;
     let counts = crucible.counts();
 
     if counts.cracked == 0 {
@@ -157,7 +151,7 @@ pub async fn analyze_and_prepare(
 
         // Ask user if they want to force retry all cracked ingots
         if ask_force_retry(counts.cracked) {
-            let mut crucible = Crucible::load(crucible_path)?;
+            let mut crucible = Crucible::load(&crucible_path)?;
             for id in &cracked_ids {
                 if let Some(ingot) = crucible.get_mut(id) {
                     ingot.status = Status::Ore;
@@ -179,7 +173,7 @@ pub async fn analyze_and_prepare(
 
 /// Detect the failure pattern for a cracked ingot by reading logs
 fn detect_failure_pattern(ingot: &Ingot) -> FailurePattern {
-    let log_dir = Path::new(crate::config::LOG_DIR);
+    let log_dir = project_path(&paths().log_dir);
 
     // Collect all matching log files (sorted by time, newest first)
     let mut matching_logs: Vec<_> = Vec::new();
@@ -395,9 +389,11 @@ async fn regenerate_cracked(
         new_ingots.len()
     );
 
-    // Remove all cracked ingots
+    // Remove all cracked ingots, pointing their dependents at the replacements
+    let new_ids: Vec<String> = new_ingots.iter().map(|i| i.id.clone()).collect();
     for id in cracked_ids {
         crucible.ingots.retain(|i| i.id != *id);
+        crucible.redirect_deps(id, &new_ids);
     }
 
     // Add new ingots (they're already marked as ore with smelt=0)
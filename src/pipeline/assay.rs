@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::config::CRUCIBLE;
+use crate::config::{paths, project_path};
 use crate::crucible::Crucible;
 use crate::error::SlagError;
 use crate::sexp::Status;
@@ -8,7 +6,7 @@ use crate::tui;
 
 /// Phase 4: Final report
 pub fn show(elapsed_secs: Option<u64>) -> Result<(), SlagError> {
-    let crucible = Crucible::load(Path::new(CRUCIBLE))?;
+    let crucible = Crucible::load(&project_path(&paths().crucible))?;
     let counts = crucible.counts();
 
     tui::header("ASSAY");
@@ -27,6 +25,10 @@ pub fn show(elapsed_secs: Option<u64>) -> Result<(), SlagError> {
 
     tui::temper_bar(&counts);
 
+    for line in crate::smith::usage::summary_lines() {
+        println!("  \x1b[90m$ {line}\x1b[0m");
+    }
+
     if counts.cracked > 0 {
         println!("\n  \x1b[31mCracked:\x1b[0m");
         for ingot in &crucible.ingots {
@@ -36,9 +38,18 @@ pub fn show(elapsed_secs: Option<u64>) -> Result<(), SlagError> {
         }
     }
 
-    println!("\n  \x1b[90mblueprint: {}\x1b[0m", crate::config::BLUEPRINT);
-    println!("  \x1b[90mcrucible:  {}\x1b[0m", crate::config::CRUCIBLE);
-    println!("  \x1b[90mslag heap: {}\x1b[0m", crate::config::LOG_DIR);
+    println!(
+        "\n  \x1b[90mblueprint: {}\x1b[0m",
+        project_path(&paths().blueprint).display()
+    );
+    println!(
+        "  \x1b[90mcrucible:  {}\x1b[0m",
+        project_path(&paths().crucible).display()
+    );
+    println!(
+        "  \x1b[90mslag heap: {}\x1b[0m",
+        project_path(&paths().log_dir).display()
+    );
 
     if counts.cracked > 0 {
         println!("\n  \x1b[31m\x1b[1m✗ CRACKED\x1b[0m\n");
@@ -1,18 +1,28 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::anvil::worktree;
-use crate::config::{PipelineConfig, SmithConfig, CRUCIBLE, LEDGER};
+use tokio::task::JoinSet;
+
+use crate::anvil::{edits, worktree};
+use crate::config::{paths, project_path, settings, Phase, PipelineConfig};
+use crate::crucible::service::{CrucibleHandle, CrucibleService};
 use crate::crucible::Crucible;
 use crate::error::SlagError;
 use crate::flux;
-use crate::proof;
-use crate::sexp::{Ingot, Status};
-use crate::smith::claude::ClaudeSmith;
-use crate::smith::Smith;
+use crate::proof::{self, Report};
+use crate::sandbox::{self, Sandbox};
+use crate::sexp::{Ingot, Status, SESSION_FIELD};
+use crate::shutdown;
+use crate::smith::session::Session;
+use crate::smith::usage::{self, Scope};
+use crate::smith::{Smith, SmithFactory, Tap};
 use crate::tui;
 
 use super::resmelt;
 
+/// How much of each candidate's diff the pick prompt shows
+const CANDIDATE_DIFF_CHARS: usize = 6000;
+
 /// Result of forging an ingot, including branch name if worktree mode
 #[derive(Debug, Clone)]
 pub struct ForgeResult {
@@ -21,181 +31,286 @@ pub struct ForgeResult {
     pub worktree_path: Option<String>,
 }
 
-/// Phase 3: Forge loop — parallel anvils then sequential
+/// Final result of one anvil task
+enum AnvilOutcome {
+    Forged(ForgeResult),
+    /// Strike failed but re-smelt produced replacement ingot(s)
+    Resmelted(Vec<Ingot>),
+    Cracked,
+    /// Budget ran out before the next heat; the ingot goes back to ore
+    Shelved,
+}
+
+/// Phase 3: Forge loop — a streaming pool of anvils.
+/// The crucible service holds the authoritative state; the coordinator
+/// starts the next eligible ingot as soon as any anvil frees up. Ready
+/// ingots (deps forged, or `:solo t`) share the pool; sequential ingots run alone.
 /// Returns list of forged branches (empty if not using worktree mode)
 pub async fn run(
-    config: &SmithConfig,
+    smiths: &dyn SmithFactory,
     pipeline_config: &PipelineConfig,
 ) -> Result<Vec<ForgeResult>, SlagError> {
     let mut forged_results: Vec<ForgeResult> = Vec::new();
-    let use_worktree = pipeline_config.worktree;
-    let max_anvils = pipeline_config.max_anvils;
+    let max_anvils = pipeline_config.max_anvils.max(1);
+    let budget = pipeline_config.budget;
+
+    // Ctrl-C is handled here so anvils can be put back cleanly
+    let _graceful = shutdown::GracefulScope::enter();
+    let crucible = CrucibleService::spawn(Crucible::load(&project_path(&paths().crucible))?);
+    let mut anvils: JoinSet<AnvilOutcome> = JoinSet::new();
+    let mut in_flight: HashMap<tokio::task::Id, String> = HashMap::new();
+    // Set while a sequential ingot holds the whole forge
+    let mut exclusive = false;
 
     loop {
-        let mut crucible = Crucible::load(Path::new(CRUCIBLE))?;
+        let mut view = crucible.snapshot().await?;
 
-        if !crucible.has_pending() {
-            // Check for cracked
-            let counts = crucible.counts();
-            if counts.cracked > 0 {
-                return Err(SlagError::ForgeFailed(counts.cracked));
-            }
-            return Ok(forged_results);
+        // Fill every free anvil with the next eligible ingot
+        while !exclusive
+            && anvils.len() < max_anvils
+            && !shutdown::is_requested()
+            && !usage::over_budget(budget)
+        {
+            let ingot = if let Some(ready) = view.ready_ore().first() {
+                (*ready).clone()
+            } else if anvils.is_empty() {
+                match view.sequential_ore() {
+                    Some(next) => {
+                        exclusive = true;
+                        next.clone()
+                    }
+                    None => break,
+                }
+            } else {
+                break;
+            };
+
+            view.set_status(&ingot.id, Status::Molten);
+            crucible.set_status(&ingot.id, Status::Molten);
+
+            println!(
+                "\n  \x1b[38;5;208m⚒ ANVIL {}/{}\x1b[0m \x1b[1;37m{}\x1b[0m  \x1b[38;5;208m◐\x1b[0m forging...  \x1b[90m{}\x1b[0m",
+                anvils.len() + 1,
+                max_anvils,
+                ingot.id,
+                tui::truncate(&ingot.work, 40),
+            );
+
+            let smith = smiths.forge(&ingot);
+            let resmelter = smiths.phase(resmelt::phase_for(&ingot));
+            let picker = (pipeline_config.fanout_pick && ingot.fanout(pipeline_config.fanout) > 1)
+                .then(|| smiths.phase(Phase::Review));
+            let config = pipeline_config.clone();
+            let handle = crucible.clone();
+            let id = ingot.id.clone();
+            let task = anvils.spawn(async move {
+                run_anvil(ingot, smith, resmelter, picker, config, handle).await
+            });
+            in_flight.insert(task.id(), id);
         }
 
-        // --- Parallel anvils for :solo t ---
-        let solo_ids: Vec<String> = crucible
-            .solo_ore()
-            .iter()
-            .take(max_anvils)
-            .map(|i| i.id.clone())
-            .collect();
-
-        if !solo_ids.is_empty() {
-            // Mark as molten
-            for id in &solo_ids {
-                crucible.set_status(id, Status::Molten);
-            }
-            crucible.save()?;
-
-            // Snapshot ingots before spawning (each task gets its own copy)
-            let ingot_snapshots: Vec<Ingot> = solo_ids
-                .iter()
-                .filter_map(|id| crucible.get(id).cloned())
-                .collect();
-
-            println!("\n  \x1b[38;5;208m⚒ ANVILS [{}]\x1b[0m", solo_ids.len());
-            let last_idx = ingot_snapshots.len().saturating_sub(1);
-            for (i, ingot) in ingot_snapshots.iter().enumerate() {
-                let prefix = if i == last_idx { "└─" } else { "├─" };
-                println!(
-                    "  \x1b[90m{}\x1b[0m \x1b[1;37m{}\x1b[0m  \x1b[38;5;208m◐\x1b[0m forging...  \x1b[90m{}\x1b[0m",
-                    prefix,
-                    ingot.id,
-                    tui::truncate(&ingot.work, 40),
-                );
+        // Make sure smiths starting now see their ingots as molten in PLAN.md
+        crucible.flush().await?;
+
+        if anvils.is_empty() {
+            // Stopped as the last anvil finished: the rest waits for `slag resume`
+            if shutdown::is_requested() {
+                crucible.shutdown().await?;
+                return Err(SlagError::Interrupted);
             }
 
-            // Spawn parallel tasks
-            let mut set = tokio::task::JoinSet::new();
-            for ingot in ingot_snapshots {
-                let smith_cmd = config.select(ingot.skill.as_str(), ingot.grade).to_string();
-                let worktree_mode = use_worktree;
-                set.spawn(async move {
-                    let smith = ClaudeSmith::new(smith_cmd);
-                    let result = strike_ingot(&ingot, &smith, worktree_mode).await;
-                    (ingot.id.clone(), result)
+            // Out of money: leave the rest for `slag resume`
+            if usage::over_budget(budget) && view.counts().ore > 0 {
+                crucible.shutdown().await?;
+                return Err(SlagError::BudgetSpent {
+                    spent: usage::spent(),
+                    limit: budget,
                 });
             }
 
-            // Collect results and update crucible on main thread
-            while let Some(result) = set.join_next().await {
-                let mut crucible = Crucible::load(Path::new(CRUCIBLE))?;
-                match result {
-                    Ok((id, Ok(forge_result))) => {
-                        crucible.set_status(&id, Status::Forged);
-                        crucible.save()?;
-                        forged_results.push(forge_result);
-                    }
-                    Ok((id, Err(_))) => {
-                        // Try resmelt
-                        if let Some(ingot) = crucible.get(&id).cloned() {
-                            let smith = ClaudeSmith::base(config);
-                            if resmelt::resmelt_ingot(&mut crucible, &ingot, &smith)
-                                .await
-                                .is_ok()
-                            {
-                                crucible.save()?;
-                            } else {
-                                crucible.set_status(&id, Status::Cracked);
-                                crucible.save()?;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("  \x1b[31m✗\x1b[0m anvil panicked: {e}");
-                    }
-                }
+            // Nothing running and nothing can start
+            let blocked = view.blocked_ore();
+            if !blocked.is_empty() {
+                let ids: Vec<&str> = blocked.iter().map(|i| i.id.as_str()).collect();
+                println!(
+                    "\n  \x1b[31m⛓\x1b[0m blocked by cracked deps: {}",
+                    ids.join(", ")
+                );
             }
+            crucible.shutdown().await?;
+            let counts = view.counts();
+            if counts.cracked > 0 {
+                return Err(SlagError::ForgeFailed(counts.cracked));
+            }
+            return Ok(forged_results);
+        }
 
-            // Show status
-            let crucible = Crucible::load(Path::new(CRUCIBLE))?;
-            print!("\n  ");
-            tui::ingot_status_line(&crucible.counts());
-            println!();
+        // A finished anvil is recorded before a stop request is acted on
+        let joined = tokio::select! {
+            biased;
+            joined = anvils.join_next_with_id() => joined,
+            _ = shutdown::requested() => {
+                stop_anvils(&mut anvils, &mut in_flight, &crucible).await?;
+                return Err(SlagError::Interrupted);
+            }
+        };
+        let Some(joined) = joined else {
             continue;
+        };
+        exclusive = false;
+        if let Some(forge_result) = apply_outcome(joined, &mut in_flight, &crucible) {
+            forged_results.push(forge_result);
         }
 
-        // --- Sequential for :solo nil ---
-        let ingot = match crucible.next_ore() {
-            Some(i) => i.clone(),
-            None => continue,
-        };
+        let view = crucible.snapshot().await?;
+        print!("\n  ");
+        tui::ingot_status_line(&view.counts());
+        println!();
+    }
+}
 
-        crucible.set_status(&ingot.id, Status::Molten);
-        crucible.save()?;
+/// Record a finished anvil in the crucible; returns the result if it forged
+fn apply_outcome(
+    joined: Result<(tokio::task::Id, AnvilOutcome), tokio::task::JoinError>,
+    in_flight: &mut HashMap<tokio::task::Id, String>,
+    crucible: &CrucibleHandle,
+) -> Option<ForgeResult> {
+    let (id, outcome) = match joined {
+        Ok((task_id, outcome)) => (in_flight.remove(&task_id), outcome),
+        Err(e) => {
+            eprintln!("  \x1b[31m✗\x1b[0m anvil panicked: {e}");
+            (in_flight.remove(&e.id()), AnvilOutcome::Cracked)
+        }
+    };
+    let id = id?;
 
-        let smith_cmd = config.select(ingot.skill.as_str(), ingot.grade).to_string();
-        let smith = ClaudeSmith::new(smith_cmd);
+    match outcome {
+        AnvilOutcome::Forged(forge_result) => {
+            crucible.set_status(&id, Status::Forged);
+            return Some(forge_result);
+        }
+        // Re-smelted: replacements come back as ore
+        AnvilOutcome::Resmelted(replacements) => crucible.replace(&id, replacements),
+        AnvilOutcome::Cracked => crucible.set_status(&id, Status::Cracked),
+        AnvilOutcome::Shelved => {
+            crucible.set_status(&id, Status::Ore);
+            println!("    \x1b[90m↳ [{id}] back to ore, budget spent\x1b[0m");
+        }
+    }
+    None
+}
 
-        match strike_ingot(&ingot, &smith, use_worktree).await {
-            Ok(forge_result) => {
-                let mut crucible = Crucible::load(Path::new(CRUCIBLE))?;
-                crucible.set_status(&ingot.id, Status::Forged);
-                crucible.save()?;
-                forged_results.push(forge_result);
-            }
-            Err(_) => {
-                let mut crucible = Crucible::load(Path::new(CRUCIBLE))?;
-                let base_smith = ClaudeSmith::base(config);
-                if resmelt::resmelt_ingot(&mut crucible, &ingot, &base_smith)
-                    .await
-                    .is_ok()
-                {
-                    // Re-smelted: status already updated by resmelt
-                    crucible.save()?;
-                } else {
-                    crucible.set_status(&ingot.id, Status::Cracked);
-                    crucible.save()?;
-                }
-            }
+/// Ctrl-C: cancel every running anvil (dropping its smith kills the smith's
+/// process group), put interrupted ingots back to ore with their heat,
+/// remove their worktrees and flush the crucible.
+async fn stop_anvils(
+    anvils: &mut JoinSet<AnvilOutcome>,
+    in_flight: &mut HashMap<tokio::task::Id, String>,
+    crucible: &CrucibleHandle,
+) -> Result<(), SlagError> {
+    anvils.abort_all();
+    while let Some(joined) = anvils.join_next_with_id().await {
+        // Anvils that finished before the abort keep their result
+        if matches!(&joined, Err(e) if e.is_cancelled()) {
+            continue;
         }
+        apply_outcome(joined, in_flight, crucible);
+    }
 
-        let crucible = Crucible::load(Path::new(CRUCIBLE))?;
-        print!("\n  ");
-        tui::ingot_status_line(&crucible.counts());
-        println!();
+    for id in in_flight.values() {
+        crucible.set_status(id, Status::Ore);
+        // Fanout candidates have worktrees even without worktree mode
+        worktree::discard_with_candidates(id).await;
+        println!("    \x1b[90m↳ [{id}] back to ore\x1b[0m");
     }
+    in_flight.clear();
+    crucible.shutdown().await
 }
 
-/// Strike a single ingot: retry with heat, extract CMD, verify proof.
-/// If worktree_mode is true, creates an isolated worktree branch for the work.
-async fn strike_ingot(
-    ingot: &Ingot,
-    smith: &dyn Smith,
-    worktree_mode: bool,
-) -> Result<ForgeResult, SlagError> {
-    let mut slag: Option<String> = None;
-    let mut worktree_path: Option<String> = None;
-    let branch_name = format!("forge/{}", ingot.id);
+/// One anvil: strike the ingot, and re-smelt it if every heat fails
+async fn run_anvil(
+    ingot: Ingot,
+    smith: Box<dyn Smith>,
+    resmelter: Box<dyn Smith>,
+    picker: Option<Box<dyn Smith>>,
+    config: PipelineConfig,
+    crucible: CrucibleHandle,
+) -> AnvilOutcome {
+    let struck = strike_ingot(&ingot, smith.into(), picker.as_deref(), &config, &crucible).await;
+    match struck {
+        Ok(forge_result) => AnvilOutcome::Forged(forge_result),
+        Err(SlagError::BudgetSpent { .. }) => AnvilOutcome::Shelved,
+        // No money left to re-smelt with: shelve it for `slag resume`
+        Err(_) if usage::over_budget(config.budget) => AnvilOutcome::Shelved,
+        Err(_) => match resmelt::resmelt_ingot(&ingot, &*resmelter).await {
+            Ok(replacements) => AnvilOutcome::Resmelted(replacements),
+            Err(_) => AnvilOutcome::Cracked,
+        },
+    }
+}
 
-    // Create worktree if in worktree mode
-    if worktree_mode {
-        match worktree::create(&ingot.id).await {
-            Ok(path) => {
-                worktree_path = Some(path.clone());
-                println!(
-                    "    \x1b[90m↳ worktree: {}\x1b[0m",
-                    tui::truncate(&path, 40)
-                );
-            }
-            Err(e) => {
-                eprintln!("    \x1b[31m✗\x1b[0m worktree create failed: {e}");
-                return Err(e);
+/// How one heat ended
+enum Heat {
+    /// CMD and proof passed; the work is not committed yet
+    Passed(Report),
+    /// Slag for the next heat. `smith_failed` when the smith itself errored,
+    /// rather than timing out, losing its backend or doing bad work.
+    Failed { slag: String, smith_failed: bool },
+}
+
+/// A heat's status line. A lone strike prints it as the heat goes; fanout
+/// candidates print theirs whole, so parallel heats don't interleave.
+struct HeatLine {
+    buffer: Option<String>,
+}
+
+impl HeatLine {
+    fn live(prefix: &str) -> Self {
+        print!("{prefix}");
+        Self { buffer: None }
+    }
+
+    fn buffered(prefix: &str) -> Self {
+        Self {
+            buffer: Some(prefix.to_string()),
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        self.buffer.is_none()
+    }
+
+    fn part(&mut self, text: &str) {
+        match &mut self.buffer {
+            Some(buffer) => buffer.push_str(text),
+            None => {
+                print!("{text}");
+                tui::flush();
             }
         }
     }
 
+    fn end(mut self) {
+        match self.buffer.take() {
+            Some(buffer) => println!("{buffer}"),
+            None => println!(),
+        }
+    }
+}
+
+/// `[██░░░] 2/5 ` in the heat's color
+fn heat_prefix(heat: u8, max: u8) -> String {
+    let hc = match heat {
+        1..=2 => "\x1b[31m",
+        3 => "\x1b[38;5;208m",
+        4 => "\x1b[38;5;220m",
+        _ => "\x1b[1;37m",
+    };
+    format!("    {hc}{} {heat}/{max}\x1b[0m ", tui::heat_bar(heat, max))
+}
+
+/// Print the ingot's header before its first heat
+fn announce(ingot: &Ingot) {
     println!(
         "\n  \x1b[38;5;208m▣\x1b[0m \x1b[1;37m[{}]\x1b[0m {}{}{}",
         ingot.id,
@@ -217,134 +332,483 @@ async fn strike_ingot(
         ingot.skill,
         tui::truncate(&ingot.proof, 30),
     );
+}
 
-    for heat in 1..=ingot.max {
-        // Update heat in crucible file
-        {
-            let mut crucible = Crucible::load(Path::new(CRUCIBLE))?;
-            crucible.increment_heat(&ingot.id);
-            crucible.save()?;
-        }
+/// Strike a single ingot: retry with heat, extract CMD, verify proof.
+/// If worktree mode is on, creates an isolated worktree branch for the work.
+/// Stops before a heat once the budget is spent.
+/// With sessions on, a smith that supports them keeps one conversation for
+/// the ingot, stored in its `:session` field so `slag resume` continues it.
+/// Ingots with a fanout above one strike several candidates at once instead.
+async fn strike_ingot(
+    ingot: &Ingot,
+    smith: Arc<dyn Smith>,
+    picker: Option<&dyn Smith>,
+    config: &PipelineConfig,
+    crucible: &CrucibleHandle,
+) -> Result<ForgeResult, SlagError> {
+    let fanout = ingot.fanout(config.fanout);
+    if fanout > 1 {
+        return strike_candidates(ingot, smith, fanout, picker, config, crucible).await;
+    }
 
-        let hc = match heat {
-            1..=2 => "\x1b[31m",
-            3 => "\x1b[38;5;208m",
-            4 => "\x1b[38;5;220m",
-            _ => "\x1b[1;37m",
-        };
-        print!(
-            "    {hc}{} {heat}/{}\x1b[0m ",
-            tui::heat_bar(heat, ingot.max),
-            ingot.max
-        );
+    let mut slag: Option<String> = None;
+    let mut session =
+        (config.sessions && smith.sessions()).then(|| match ingot.extra_field(SESSION_FIELD) {
+            Some(id) => Session::resume(id),
+            None => new_session(&ingot.id, crucible),
+        });
+    let mut worktree_path: Option<String> = None;
+    let branch_name = format!("forge/{}", ingot.id);
 
-        let flux_text = flux::prepare_flux(ingot, slag.as_deref());
-        log_to_file(&format!("FLUX_{}_{heat}", ingot.id), &flux_text);
+    // Create worktree if in worktree mode
+    if config.worktree {
+        match worktree::create(&ingot.id).await {
+            Ok(path) => {
+                worktree_path = Some(path.clone());
+                println!(
+                    "    \x1b[90m↳ worktree: {}\x1b[0m",
+                    tui::truncate(&path, 40)
+                );
+            }
+            Err(e) => {
+                eprintln!("    \x1b[31m✗\x1b[0m worktree create failed: {e}");
+                return Err(e);
+            }
+        }
+    }
 
-        let spinner_msg = if ingot.is_complex() {
-            "planning..."
-        } else if ingot.is_web() {
-            "web forging..."
-        } else {
-            "forging..."
-        };
-        let spinner = tui::spinner(spinner_msg);
+    announce(ingot);
 
-        // In worktree mode, invoke smith in the worktree directory
-        let response = if let Some(ref wt_path) = worktree_path {
-            invoke_smith_in_worktree(smith, &flux_text, wt_path).await
-        } else {
-            smith.invoke(&flux_text).await
-        };
+    for heat in 1..=ingot.max {
+        if usage::over_budget(config.budget) {
+            if worktree_path.is_some() {
+                worktree::discard(&ingot.id).await;
+            }
+            return Err(budget_spent(config.budget));
+        }
+        crucible.increment_heat(&ingot.id);
+
+        let mut line = HeatLine::live(&heat_prefix(heat, ingot.max));
+        let struck = strike_heat(
+            ingot,
+            &*smith,
+            heat,
+            slag.as_deref(),
+            session.as_ref(),
+            worktree_path.as_deref(),
+            &ingot.id,
+            &mut line,
+        )
+        .await;
+        line.end();
 
-        let response = match response {
-            Ok(r) => {
-                spinner.finish_and_clear();
-                r
+        match struck {
+            Heat::Passed(report) => {
+                // Commit in worktree or main repo
+                if let Some(ref wt_path) = worktree_path {
+                    git_commit_in_dir(&ingot.id, &ingot.work, wt_path).await;
+                } else {
+                    proof::git_commit(&ingot.id, &ingot.work).await;
+                }
+
+                append_ledger(ingot, heat, &report);
+                return Ok(ForgeResult {
+                    id: ingot.id.clone(),
+                    branch: if config.worktree {
+                        Some(branch_name)
+                    } else {
+                        None
+                    },
+                    worktree_path,
+                });
             }
-            Err(e) => {
-                spinner.finish_and_clear();
-                slag = Some(format!("Smith error: {e}"));
-                println!("\x1b[31m✗\x1b[0m");
-                continue;
+            Heat::Failed {
+                slag: next,
+                smith_failed,
+            } => {
+                if let Some(s) = &mut session {
+                    // The agent failed outright, maybe over the session itself: start over
+                    if smith_failed {
+                        *s = new_session(&ingot.id, crucible);
+                    } else {
+                        s.resume = true;
+                    }
+                }
+                slag = Some(next);
             }
-        };
+        }
+    }
 
-        log_to_file(&format!("STRIKE_{}_{heat}", ingot.id), &response);
+    // Clean up worktree on failure (preserve branch for debugging)
+    if worktree_path.is_some() {
+        worktree::cleanup_without_merge(&ingot.id).await;
+    }
 
-        // Extract CMD
-        let cmd = match proof::extract_cmd(&response) {
-            Some(c) => c,
-            None => {
-                slag = Some("NO CMD: line in response".into());
-                println!("\x1b[31m✗\x1b[0m smith output missing \"CMD:\" line");
-                continue;
-            }
-        };
+    Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max))
+}
 
-        print!("\x1b[90m{}\x1b[0m ", tui::truncate(&cmd, 32));
-        tui::flush();
+/// Strike `fanout` candidates of one ingot at once, each in its own
+/// worktree (`forge/<id>.<n>`), each heat carrying its own slag. The first
+/// to pass its proof is kept, or with a picker the review smith chooses
+/// among all that passed; the rest are discarded. The kept candidate
+/// becomes the ingot's `forge/<id>` branch, merged right away unless
+/// worktree mode leaves it for review.
+async fn strike_candidates(
+    ingot: &Ingot,
+    smith: Arc<dyn Smith>,
+    fanout: u8,
+    picker: Option<&dyn Smith>,
+    config: &PipelineConfig,
+    crucible: &CrucibleHandle,
+) -> Result<ForgeResult, SlagError> {
+    let slots: Vec<String> = (1..=fanout).map(|n| format!("{}.{n}", ingot.id)).collect();
+    let mut paths = Vec::with_capacity(slots.len());
+    for slot in &slots {
+        match worktree::create(slot).await {
+            Ok(path) => paths.push(path),
+            Err(e) => {
+                eprintln!("    \x1b[31m✗\x1b[0m worktree create failed: {e}");
+                discard_all(&slots).await;
+                return Err(e);
+            }
+        }
+    }
+    println!(
+        "    \x1b[90m↳ fanout: {fanout} candidates in ../slag-anvil-{}.1-{fanout}\x1b[0m",
+        ingot.id
+    );
+    announce(ingot);
 
-        // Run CMD (in worktree if applicable)
-        let (ok, output) = if let Some(ref wt_path) = worktree_path {
-            run_shell_in_dir(&cmd, wt_path).await
-        } else {
-            proof::run_shell(&cmd).await
-        };
-        log_to_file(
-            &format!("ASSAY_{}_{heat}", ingot.id),
-            &format!("exit={}\n{output}", if ok { 0 } else { 1 }),
-        );
+    let mut slags: Vec<Option<String>> = vec![None; slots.len()];
+    for heat in 1..=ingot.max {
+        if usage::over_budget(config.budget) {
+            discard_all(&slots).await;
+            return Err(budget_spent(config.budget));
+        }
+        crucible.increment_heat(&ingot.id);
+
+        let mut strikes = JoinSet::new();
+        for (i, (slot, path)) in slots.iter().zip(&paths).enumerate() {
+            let (ingot, smith) = (ingot.clone(), smith.clone());
+            let (slot, path, slag) = (slot.clone(), path.clone(), slags[i].clone());
+            let prefix = format!("{}\x1b[90m#{}\x1b[0m ", heat_prefix(heat, ingot.max), i + 1);
+            // Candidates run without sessions: one conversation can't fork
+            strikes.spawn(async move {
+                let mut line = HeatLine::buffered(&prefix);
+                let struck = strike_heat(
+                    &ingot,
+                    &*smith,
+                    heat,
+                    slag.as_deref(),
+                    None,
+                    Some(&path),
+                    &slot,
+                    &mut line,
+                )
+                .await;
+                line.end();
+                (i, struck)
+            });
+        }
 
-        if ok {
-            // Verify proof if different from cmd
-            if !ingot.proof.is_empty() && ingot.proof != cmd && ingot.proof != "true" {
-                let (proof_ok, proof_output) = if let Some(ref wt_path) = worktree_path {
-                    run_shell_in_dir(&ingot.proof, wt_path).await
-                } else {
-                    proof::run_shell(&ingot.proof).await
-                };
-                if !proof_ok {
-                    slag = Some(format!("Proof failed [{}]: {proof_output}", ingot.proof));
-                    println!(
-                        "\x1b[31m✗\x1b[0m proof failed: {} (exit 1)",
-                        tui::truncate(&ingot.proof, 30)
-                    );
-                    continue;
+        let mut passed = Vec::new();
+        let mut reports = vec![Report::default(); slots.len()];
+        while let Some(joined) = strikes.join_next().await {
+            let Ok((i, struck)) = joined else { continue };
+            match struck {
+                Heat::Passed(report) => {
+                    passed.push(i);
+                    reports[i] = report;
+                    // The first to pass wins
+                    if picker.is_none() {
+                        break;
+                    }
                 }
+                Heat::Failed { slag, .. } => slags[i] = Some(slag),
             }
+        }
+        strikes.shutdown().await;
 
-            println!("\x1b[1;37m█\x1b[0m");
+        let Some(&first) = passed.first() else {
+            continue;
+        };
+        let kept = match picker {
+            Some(picker) if passed.len() > 1 => {
+                pick_candidate(picker, ingot, &passed, &paths).await
+            }
+            _ => first,
+        };
 
-            // Commit in worktree or main repo
-            if let Some(ref wt_path) = worktree_path {
-                git_commit_in_dir(&ingot.id, &ingot.work, wt_path).await;
-            } else {
-                proof::git_commit(&ingot.id, &ingot.work).await;
+        git_commit_in_dir(&ingot.id, &ingot.work, &paths[kept]).await;
+        for (i, slot) in slots.iter().enumerate() {
+            if i != kept {
+                worktree::discard(slot).await;
             }
+        }
+        let path = match worktree::adopt(&slots[kept], &ingot.id).await {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("    \x1b[31m✗\x1b[0m {e}");
+                worktree::discard(&slots[kept]).await;
+                return Err(e);
+            }
+        };
+        println!(
+            "    \x1b[90m↳ kept candidate #{} ({} passed)\x1b[0m",
+            kept + 1,
+            passed.len()
+        );
+        append_ledger(ingot, heat, &reports[kept]);
 
-            append_ledger(ingot, heat);
+        if config.worktree {
             return Ok(ForgeResult {
                 id: ingot.id.clone(),
-                branch: if worktree_mode {
-                    Some(branch_name)
-                } else {
-                    None
-                },
-                worktree_path,
+                branch: Some(format!("forge/{}", ingot.id)),
+                worktree_path: Some(path),
             });
+        }
+        worktree::merge_and_cleanup(&ingot.id).await?;
+        return Ok(ForgeResult {
+            id: ingot.id.clone(),
+            branch: None,
+            worktree_path: None,
+        });
+    }
+
+    discard_all(&slots).await;
+    Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max))
+}
+
+/// One heat: send the flux, apply file blocks, run the CMD and check the
+/// proof. Logs go under `key`, the ingot id or a fanout candidate's slot.
+#[allow(clippy::too_many_arguments)]
+async fn strike_heat(
+    ingot: &Ingot,
+    smith: &dyn Smith,
+    heat: u8,
+    slag: Option<&str>,
+    session: Option<&Session>,
+    worktree_path: Option<&str>,
+    key: &str,
+    line: &mut HeatLine,
+) -> Heat {
+    let failed = |slag: String| Heat::Failed {
+        slag,
+        smith_failed: false,
+    };
+
+    // A continued session already holds the forge order
+    let mut flux_text = match session {
+        Some(s) if s.resume => flux::prepare_session_flux(ingot, heat, slag),
+        _ => flux::prepare_flux(ingot, slag),
+    };
+    if !smith.edits_files() {
+        flux_text.push_str(flux::FILE_BLOCK_PROTOCOL);
+    }
+    log_to_file(&format!("FLUX_{key}_{heat}"), &flux_text);
+
+    // Only the project is writable, or in a worktree only the worktree and
+    // the git directory its commits go to
+    let sandbox = match Sandbox::for_skill(&settings().sandbox, ingot.skill.as_str()) {
+        Some(s) => Some(match worktree_path {
+            Some(wt_path) => match worktree::common_dir().await {
+                Some(git_dir) => s.writable(wt_path).writable(git_dir),
+                None => s.writable(wt_path),
+            },
+            None => s.writable(std::env::current_dir().unwrap_or_else(|_| ".".into())),
+        }),
+        None => None,
+    };
+
+    let spinner_msg = if ingot.is_complex() {
+        "planning..."
+    } else if ingot.is_web() {
+        "web forging..."
+    } else {
+        "forging..."
+    };
+    // Candidates share the terminal, so only a lone strike spins
+    let spinner = if line.is_live() {
+        tui::spinner(spinner_msg)
+    } else {
+        indicatif::ProgressBar::hidden()
+    };
+
+    // Stream the smith's output into the log, and its latest action onto the spinner
+    let (activity_tx, mut activity_rx) = tokio::sync::watch::channel(String::new());
+    let tap = Tap {
+        log: Some(log_path(&format!("STREAM_{key}_{heat}"))),
+        activity: Some(activity_tx),
+        session: session.cloned(),
+        // The agent needs the network to reach its model
+        sandbox: sandbox.clone().map(Sandbox::online),
+    };
+    let live = spinner.clone();
+    let watcher = tokio::spawn(async move {
+        while activity_rx.changed().await.is_ok() {
+            let activity = activity_rx.borrow_and_update().clone();
+            live.set_message(format!(
+                "{spinner_msg} \x1b[90m↳ {}\x1b[0m",
+                tui::truncate(&activity, 50)
+            ));
+        }
+    });
+
+    // In worktree mode, invoke smith in the worktree directory
+    let strike = async {
+        if let Some(wt_path) = worktree_path {
+            invoke_smith_in_worktree(smith, &flux_text, wt_path, &tap).await
         } else {
-            slag = Some(format!("CMD failed (exit 1): {output}"));
-            println!("\x1b[31m✗\x1b[0m");
+            smith.invoke_tapped(&flux_text, &tap).await
+        }
+    };
+    let (response, _) = usage::metered(Scope::heat(&ingot.id, heat), strike).await;
+    watcher.abort();
+    spinner.finish_and_clear();
+
+    let response = match response {
+        Ok(r) => r,
+        Err(SlagError::SmithTimeout(secs)) => {
+            line.part(&format!("\x1b[31m✗\x1b[0m timed out after {secs}s"));
+            return failed(flux::timeout_slag(secs));
+        }
+        // Retries already ran out; this heat is lost to the backend, not the code
+        Err(SlagError::SmithTransient(detail)) => {
+            line.part("\x1b[31m✗\x1b[0m backend unavailable");
+            return failed(flux::transient_slag(&detail));
+        }
+        Err(e) => {
+            line.part("\x1b[31m✗\x1b[0m");
+            return Heat::Failed {
+                smith_failed: matches!(e, SlagError::SmithFailed(_)),
+                slag: format!("Smith error: {e}"),
+            };
+        }
+    };
+
+    log_to_file(&format!("STRIKE_{key}_{heat}"), &response);
+
+    // Text-only smiths send their edits as file blocks
+    if !smith.edits_files() {
+        let root = std::path::Path::new(worktree_path.unwrap_or("."));
+        match edits::parse(&response).and_then(|blocks| edits::apply(&blocks, root)) {
+            Ok(written) if !written.is_empty() => {
+                line.part(&format!("\x1b[90m✎ {}\x1b[0m ", written.len()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                line.part(&format!("\x1b[31m✗\x1b[0m {e}"));
+                return failed(e.to_string());
+            }
         }
     }
 
-    // Clean up worktree on failure (preserve branch for debugging)
-    if worktree_path.is_some() {
-        worktree::cleanup_without_merge(&ingot.id).await;
+    // Extract the report, or at least its CMD
+    let Some(report) = proof::extract_report(&response) else {
+        line.part("\x1b[31m✗\x1b[0m smith output missing report or \"CMD:\" line");
+        return failed("NO CMD: no report block or CMD: line in response".into());
+    };
+    let cmd = &report.cmd;
+
+    line.part(&format!("\x1b[90m{}\x1b[0m ", tui::truncate(cmd, 32)));
+
+    // Run CMD (in worktree if applicable)
+    let dir = worktree_path.unwrap_or(".");
+    let (ok, output) = run_shell_in_dir(cmd, dir, sandbox.as_ref()).await;
+    log_to_file(
+        &format!("ASSAY_{key}_{heat}"),
+        &format!("exit={}\n{output}", if ok { 0 } else { 1 }),
+    );
+    if !ok {
+        line.part("\x1b[31m✗\x1b[0m");
+        return failed(format!("CMD failed (exit 1): {output}"));
     }
 
-    Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max))
+    // Verify proof if different from cmd
+    if !ingot.proof.is_empty() && ingot.proof != *cmd && ingot.proof != "true" {
+        let (proof_ok, proof_output) = run_shell_in_dir(&ingot.proof, dir, sandbox.as_ref()).await;
+        if !proof_ok {
+            line.part(&format!(
+                "\x1b[31m✗\x1b[0m proof failed: {} (exit 1)",
+                tui::truncate(&ingot.proof, 30)
+            ));
+            return failed(format!("Proof failed [{}]: {proof_output}", ingot.proof));
+        }
+    }
+
+    line.part("\x1b[1;37m█\x1b[0m");
+    Heat::Passed(report)
+}
+
+/// Ask the review smith which passing candidate to keep. Falls back to the
+/// first that passed if the smith errs or names none of them.
+async fn pick_candidate(
+    picker: &dyn Smith,
+    ingot: &Ingot,
+    passed: &[usize],
+    paths: &[String],
+) -> usize {
+    let mut candidates = Vec::with_capacity(passed.len());
+    for &i in passed {
+        candidates.push((i + 1, candidate_diff(&paths[i]).await));
+    }
+    let prompt = flux::prepare_pick_flux(ingot, &candidates);
+    log_to_file(&format!("PICK_{}", ingot.id), &prompt);
+
+    let spinner = tui::spinner(&format!("picking among {} candidates...", passed.len()));
+    let answer = picker.invoke(&prompt).await;
+    spinner.finish_and_clear();
+    let answer = match answer {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("    \x1b[31m✗\x1b[0m pick failed, keeping the first: {e}");
+            return passed[0];
+        }
+    };
+    log_to_file(&format!("PICK_RESULT_{}", ingot.id), &answer);
+
+    let picked = answer.lines().find_map(|l| {
+        l.trim()
+            .strip_prefix("PICK:")
+            .and_then(|n| n.trim().trim_start_matches('#').parse::<usize>().ok())
+    });
+    passed
+        .iter()
+        .copied()
+        .find(|&i| Some(i + 1) == picked)
+        .unwrap_or(passed[0])
+}
+
+/// A candidate's uncommitted work for the picker: stat, then the diff cut short
+async fn candidate_diff(dir: &str) -> String {
+    let git = |args: &'static [&'static str]| async move {
+        tokio::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default()
+    };
+    git(&["add", "-A"]).await;
+    let stat = git(&["diff", "--cached", "--stat"]).await;
+    let diff = git(&["diff", "--cached"]).await;
+    format!("{stat}\n{}", tui::truncate(&diff, CANDIDATE_DIFF_CHARS))
+}
+
+/// Remove every candidate's worktree and branch
+async fn discard_all(slots: &[String]) {
+    for slot in slots {
+        worktree::discard(slot).await;
+    }
+}
+
+fn budget_spent(budget: f64) -> SlagError {
+    SlagError::BudgetSpent {
+        spent: usage::spent(),
+        limit: budget,
+    }
 }
 
 /// Invoke smith in a specific directory (worktree)
@@ -352,6 +816,7 @@ async fn invoke_smith_in_worktree(
     smith: &dyn Smith,
     prompt: &str,
     worktree_path: &str,
+    tap: &Tap,
 ) -> Result<String, SlagError> {
     // The smith will work in the current directory, so we need to
     // modify the prompt to include worktree context
@@ -360,17 +825,19 @@ async fn invoke_smith_in_worktree(
         All file operations should be relative to this directory.\n\n\
         {prompt}"
     );
-    smith.invoke(&enhanced_prompt).await
+    smith.invoke_tapped(&enhanced_prompt, tap).await
 }
 
-/// Run a shell command in a specific directory
-async fn run_shell_in_dir(cmd: &str, dir: &str) -> (bool, String) {
-    match tokio::process::Command::new("bash")
-        .args(["-c", cmd])
-        .current_dir(dir)
-        .output()
-        .await
-    {
+/// Start a session for an ingot and keep its id in the crucible
+fn new_session(id: &str, crucible: &CrucibleHandle) -> Session {
+    let session = Session::start();
+    crucible.set_extra(id, SESSION_FIELD, Some(&session.id));
+    session
+}
+
+/// Run a shell command in a specific directory, sandboxed if `sandbox` is set
+async fn run_shell_in_dir(cmd: &str, dir: &str, sandbox: Option<&Sandbox>) -> (bool, String) {
+    match crate::shutdown::output(sandbox::shell(cmd, sandbox).current_dir(dir)).await {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -396,8 +863,10 @@ async fn git_commit_in_dir(id: &str, work: &str, dir: &str) {
         .await;
 }
 
-fn append_ledger(ingot: &Ingot, heat: u8) {
-    let entry = format!(
+/// Record a forged ingot, with what its smith reported, in the ledger.
+/// Later fluxes include the ledger's tail, so learnings reach later ingots.
+fn append_ledger(ingot: &Ingot, heat: u8, report: &Report) {
+    let mut entry = format!(
         "\n## {} [{}] gr:{} skill:{}\n- {}\n- heats:{}\n",
         chrono::Local::now().format("%m-%d %H:%M"),
         ingot.id,
@@ -406,10 +875,25 @@ fn append_ledger(ingot: &Ingot, heat: u8) {
         ingot.work,
         heat,
     );
+    if !report.files_touched.is_empty() {
+        entry.push_str(&format!("- files: {}\n", report.files_touched.join(", ")));
+    }
+    if let Some(learnings) = &report.learnings {
+        entry.push_str(&format!("- learned: {learnings}\n"));
+    }
+    if let Some(confidence) = report.confidence {
+        entry.push_str(&format!("- confidence: {confidence}\n"));
+    }
+    let spent = usage::for_ingot(&ingot.id);
+    let entry = if spent.is_empty() {
+        entry
+    } else {
+        format!("{entry}- spent: {}\n", spent.summary())
+    };
     let _ = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(LEDGER)
+        .open(project_path(&paths().ledger))
         .and_then(|mut f| {
             use std::io::Write;
             f.write_all(entry.as_bytes())
@@ -417,7 +901,10 @@ fn append_ledger(ingot: &Ingot, heat: u8) {
 }
 
 fn log_to_file(label: &str, content: &str) {
+    let _ = std::fs::write(log_path(label), content);
+}
+
+fn log_path(label: &str) -> std::path::PathBuf {
     let ts = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let path = format!("{}/{ts}_{label}.log", crate::config::LOG_DIR);
-    let _ = std::fs::write(&path, content);
+    project_path(&paths().log_dir).join(format!("{ts}_{label}.log"))
 }
@@ -1,4 +1,4 @@
-use crate::config::{BLUEPRINT, CRUCIBLE, MAX_ITERATE, ORE_FILE};
+use crate::config::{paths, project_path, settings};
 use crate::crucible::{self, Crucible};
 use crate::error::SlagError;
 use crate::flux;
@@ -9,8 +9,9 @@ use crate::tui;
 pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
     tui::header("FOUNDER · casting mold");
 
-    let ore = std::fs::read_to_string(ORE_FILE).map_err(|_| SlagError::NoOre)?;
-    let blueprint = std::fs::read_to_string(BLUEPRINT).unwrap_or_else(|_| "No blueprint".into());
+    let ore = std::fs::read_to_string(project_path(&paths().ore)).map_err(|_| SlagError::NoOre)?;
+    let blueprint = std::fs::read_to_string(project_path(&paths().blueprint))
+        .unwrap_or_else(|_| "No blueprint".into());
 
     let prompt = flux::founder_prompt(&ore, &blueprint);
     log_to_file("FOUNDER_PROMPT", &prompt);
@@ -25,7 +26,7 @@ pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
     log_to_file("FOUNDER_RAW", &raw);
 
     // Self-iterate if questions
-    let raw = smith::self_iterate(smith, raw, MAX_ITERATE).await?;
+    let raw = smith::self_iterate(smith, raw, settings().thresholds.max_iterate).await?;
 
     let ingots = crucible::parse_ingot_lines(&raw);
     if ingots.is_empty() {
@@ -33,8 +34,9 @@ pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
     }
 
     // Create crucible
-    let crucible_path = std::path::PathBuf::from(CRUCIBLE);
-    let crucible = Crucible::new(&crucible_path, ingots.clone());
+    let crucible_path = project_path(&paths().crucible);
+    let mut crucible = Crucible::new(&crucible_path, ingots.clone());
+    crucible.validate_deps()?;
     crucible.save()?;
 
     // Stats
@@ -78,6 +80,6 @@ pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
 
 fn log_to_file(label: &str, content: &str) {
     let ts = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let path = format!("{}/{ts}_{label}.log", crate::config::LOG_DIR);
+    let path = project_path(&paths().log_dir).join(format!("{ts}_{label}.log"));
     let _ = std::fs::write(&path, content);
 }
@@ -2,42 +2,51 @@ pub mod analysis;
 pub mod assay;
 pub mod forge;
 pub mod founder;
+pub mod recovery;
 pub mod resmelt;
 pub mod review;
 pub mod surveyor;
 
-use crate::config::{PipelineConfig, SmithConfig};
+use crate::config::{paths, project_path, Phase, PipelineConfig};
 use crate::crucible::Crucible;
 use crate::error::SlagError;
-use crate::smith::claude::ClaudeSmith;
+use crate::smith::{self, SmithFactory};
 use crate::tui;
 
 /// Run the full pipeline (4 or 5 phases depending on review mode).
 pub async fn run(
     commission: Option<&str>,
-    smith_config: &SmithConfig,
+    smiths: &dyn SmithFactory,
     pipeline_config: &PipelineConfig,
 ) -> Result<(), SlagError> {
     tui::show_banner();
 
+    // One run per state directory; a lock left by a dead run means a crash
+    ignore_in_state_dir(crate::config::RUN_LOCK)?;
+    let (_lock, previous) = recovery::RunLock::acquire()?;
+    recovery::recover(&project_path(&paths().crucible), previous.as_ref()).await?;
+
     // Fire furnace if needed
     fire_furnace(commission)?;
 
     // Phase 1: Survey
-    if !std::path::Path::new(crate::config::BLUEPRINT).exists() {
-        let smith = ClaudeSmith::plan(smith_config);
-        surveyor::run(&smith).await?;
+    if !project_path(&paths().blueprint).exists() {
+        let smith = smiths.phase(Phase::Survey);
+        surveyor::run(&*smith).await?;
     }
 
     // Phase 2: Found
-    let crucible_path = std::path::Path::new(crate::config::CRUCIBLE);
+    let crucible_path = project_path(&paths().crucible);
     let needs_founder = !crucible_path.exists() || {
-        let content = std::fs::read_to_string(crucible_path).unwrap_or_default();
+        let content = std::fs::read_to_string(&crucible_path).unwrap_or_default();
         !content.contains("(ingot ")
     };
     if needs_founder {
-        let smith = ClaudeSmith::plan(smith_config);
-        founder::run(&smith).await?;
+        let smith = smiths.phase(Phase::Found);
+        founder::run(&*smith).await?;
+
+        // Catch a broken plan before any anvil is lit
+        crate::lint::run(&crucible_path).await?;
     }
 
     // Phase 3: Forge (with retry loop)
@@ -55,29 +64,34 @@ pub async fn run(
         }
         tui::show_legend();
 
-        let crucible = Crucible::load(crucible_path)?;
+        let crucible = Crucible::load(&crucible_path)?;
         let counts = crucible.counts();
         print!("  ");
         tui::ingot_status_line(&counts);
         println!();
 
         // Run forge (ignore ForgeFailed error - we handle it with analysis)
-        let forge_result = forge::run(smith_config, pipeline_config).await;
+        let forge_result = forge::run(smiths, pipeline_config).await;
 
         let forged_branches = match forge_result {
             Ok(branches) => branches,
             Err(SlagError::ForgeFailed(_)) => Vec::new(),
+            Err(e @ SlagError::BudgetSpent { .. }) => {
+                assay::show(Some(forge_start.elapsed().as_secs()))?;
+                record_spend();
+                return Err(e);
+            }
             Err(e) => return Err(e),
         };
 
         // Phase 3.5: Review (if worktree mode enabled)
         if pipeline_config.should_review() && !forged_branches.is_empty() {
-            let smith = ClaudeSmith::base(smith_config);
-            review::run(&smith, pipeline_config, &forged_branches).await?;
+            let smith = smiths.phase(Phase::Review);
+            review::run(&*smith, pipeline_config, &forged_branches).await?;
         }
 
         // Check if we're done (all forged, none cracked)
-        let crucible = Crucible::load(crucible_path)?;
+        let crucible = Crucible::load(&crucible_path)?;
         let counts = crucible.counts();
 
         if counts.cracked == 0 {
@@ -96,8 +110,8 @@ pub async fn run(
         }
 
         // Analyze failures and prepare for retry
-        let smith = ClaudeSmith::base(smith_config);
-        let can_retry = analysis::analyze_and_prepare(&smith, smith_config, cycle).await?;
+        let smith = smiths.phase(Phase::Analysis);
+        let can_retry = analysis::analyze_and_prepare(&*smith, cycle).await?;
 
         if !can_retry {
             println!("\n  \x1b[31m✗\x1b[0m No recoverable ingots, stopping");
@@ -110,9 +124,10 @@ pub async fn run(
     // Phase 4: Assay
     let elapsed_secs = forge_start.elapsed().as_secs();
     assay::show(Some(elapsed_secs))?;
+    record_spend();
 
     // Final check - if any cracked, return error
-    let crucible = Crucible::load(crucible_path)?;
+    let crucible = Crucible::load(&crucible_path)?;
     let counts = crucible.counts();
     if counts.cracked > 0 {
         return Err(SlagError::ForgeFailed(counts.cracked));
@@ -121,9 +136,56 @@ pub async fn run(
     Ok(())
 }
 
+/// Append this run's spend per phase to the ledger
+fn record_spend() {
+    let lines = smith::usage::summary_lines();
+    if lines.is_empty() {
+        return;
+    }
+    let mut entry = format!(
+        "\n## {} spend\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    for line in lines {
+        entry.push_str(&format!("- {line}\n"));
+    }
+    let _ = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(project_path(&paths().ledger))
+        .and_then(|mut f| {
+            use std::io::Write;
+            f.write_all(entry.as_bytes())
+        });
+}
+
+/// Keep `entry` (relative to the state directory) out of git, through a
+/// `.gitignore` in the state directory so the project's own is left alone
+fn ignore_in_state_dir(entry: &str) -> Result<(), SlagError> {
+    let entry = entry.trim_start_matches("./");
+    let gitignore = project_path(".gitignore");
+    let content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|l| l.trim() == entry) {
+        return Ok(());
+    }
+    if let Some(dir) = gitignore.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&gitignore)?;
+    use std::io::Write;
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(f)?;
+    }
+    writeln!(f, "{entry}")?;
+    Ok(())
+}
+
 /// Initialize project structure (fire the furnace)
 fn fire_furnace(commission: Option<&str>) -> Result<(), SlagError> {
-    let ore_path = std::path::Path::new(crate::config::ORE_FILE);
+    let ore_path = project_path(&paths().ore);
 
     if ore_path.exists() {
         return Ok(());
@@ -139,30 +201,24 @@ fn fire_furnace(commission: Option<&str>) -> Result<(), SlagError> {
         .output();
 
     // .gitignore
-    let gitignore = std::path::Path::new(".gitignore");
-    let content = std::fs::read_to_string(gitignore).unwrap_or_default();
-    if !content.contains("logs/") {
-        let mut f = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(gitignore)?;
-        use std::io::Write;
-        writeln!(f, "logs/")?;
-    }
+    ignore_in_state_dir(&format!("{}/", paths().log_dir))?;
+
+    // State directory holds every slag file
+    std::fs::create_dir_all(project_path(&paths().log_dir))?;
 
     // Create PRD.md
     std::fs::write(ore_path, format!("# Commission\n\n{commission}\n"))?;
     tui::status_line("░", tui::COLD, "Ore loaded");
 
     // Create AGENTS.md
-    let alloy_path = std::path::Path::new(crate::config::ALLOY_FILE);
+    let alloy_path = project_path(&paths().alloy);
     if !alloy_path.exists() {
         std::fs::write(alloy_path, "## Alloy Recipes\n")?;
         tui::status_line("+", tui::COLD, "Recipes ready");
     }
 
     // Create PROGRESS.md
-    let ledger_path = std::path::Path::new(crate::config::LEDGER);
+    let ledger_path = project_path(&paths().ledger);
     if !ledger_path.exists() {
         std::fs::write(
             ledger_path,
@@ -174,9 +230,6 @@ fn fire_furnace(commission: Option<&str>) -> Result<(), SlagError> {
         tui::status_line("+", tui::COLD, "Ledger open");
     }
 
-    // Create logs dir
-    std::fs::create_dir_all(crate::config::LOG_DIR)?;
-
     // Initial commit
     let _ = std::process::Command::new("git")
         .args(["add", "-A"])
@@ -0,0 +1,223 @@
+use std::fs::{File, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::anvil::worktree;
+use crate::config::{project_path, RUN_LOCK};
+use crate::crucible::Crucible;
+use crate::error::SlagError;
+
+/// Owner recorded in the run lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub run_id: String,
+}
+
+impl LockOwner {
+    fn parse(content: &str) -> Option<Self> {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                .map(str::trim)
+        };
+        Some(LockOwner {
+            pid: field("pid")?.parse().ok()?,
+            run_id: field("run")?.to_string(),
+        })
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "pid={}\nrun={}\nstarted={}\n",
+            self.pid,
+            self.run_id,
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        )
+    }
+}
+
+/// Exclusive claim on the state directory for one slag run: an advisory
+/// lock (`flock`) on the lock file, which the OS releases if the run dies.
+/// The file is removed when the guard drops.
+#[derive(Debug)]
+pub struct RunLock {
+    path: PathBuf,
+    pub owner: LockOwner,
+    /// Holds the lock for as long as it is open
+    _file: File,
+}
+
+impl RunLock {
+    /// Take the run lock in the state directory
+    pub fn acquire() -> Result<(Self, Option<LockOwner>), SlagError> {
+        Self::acquire_at(&project_path(RUN_LOCK))
+    }
+
+    /// Take the lock at `path`. Fails if another run holds it; a lock file
+    /// left by a run that died is taken over and its owner returned for the
+    /// report.
+    pub fn acquire_at(path: &Path) -> Result<(Self, Option<LockOwner>), SlagError> {
+        let owner = LockOwner {
+            pid: std::process::id(),
+            run_id: format!(
+                "{}-{}",
+                chrono::Local::now().format("%Y%m%d%H%M%S"),
+                std::process::id()
+            ),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        loop {
+            let mut file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => return Err(held_by(path)),
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+            // The run we raced may have removed the file while letting go of
+            // it, leaving us a lock on a file nobody else will open
+            if !is_current(&file, path) {
+                continue;
+            }
+
+            // Runs remove the file on the way out, so an owner still in it died
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            let previous = LockOwner::parse(&content);
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(owner.render().as_bytes())?;
+
+            let lock = RunLock {
+                path: path.to_path_buf(),
+                owner,
+                _file: file,
+            };
+            return Ok((lock, previous));
+        }
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        // Still locked by us, so nobody else has written it
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The error for a lock someone else holds. A run that has only just taken
+/// it may not have written its owner yet, so give it a moment.
+fn held_by(path: &Path) -> SlagError {
+    for _ in 0..10 {
+        let holder = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|c| LockOwner::parse(&c));
+        if let Some(holder) = holder {
+            return SlagError::RunLocked {
+                pid: holder.pid,
+                run_id: holder.run_id,
+            };
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    SlagError::RunLocked {
+        pid: 0,
+        run_id: "starting".into(),
+    }
+}
+
+/// Whether `file` is still the one at `path`
+fn is_current(file: &File, path: &Path) -> bool {
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+/// Bring back ingots orphaned by a run that died mid-forge.
+/// Must be called while holding the run lock: any molten ingot then belongs
+/// to a dead run. Resets them to ore (heat kept), discards their worktrees
+/// and branches (fanout candidates' too), and reports what was recovered.
+pub async fn recover(
+    crucible_path: &Path,
+    previous: Option<&LockOwner>,
+) -> Result<Vec<String>, SlagError> {
+    if !crucible_path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut crucible = Crucible::load(crucible_path)?;
+    let reset = crucible.reset_molten();
+    if reset.is_empty() {
+        return Ok(reset);
+    }
+    crucible.save()?;
+
+    let origin = match previous {
+        Some(prev) => format!("run {} (pid {})", prev.run_id, prev.pid),
+        None => "an earlier run".to_string(),
+    };
+    println!("\n  \x1b[38;5;208m⟲\x1b[0m recovering from {origin}");
+    for id in &reset {
+        worktree::discard_with_candidates(id).await;
+        let heat = crucible.get(id).map(|i| i.heat).unwrap_or(0);
+        println!("    \x1b[90m↳ [{id}] molten → ore (heat {heat} kept), worktree cleared\x1b[0m");
+    }
+    Ok(reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_blocks_a_second_run_and_takes_over_a_dead_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RUN_LOCK);
+
+        let (lock, previous) = RunLock::acquire_at(&path).unwrap();
+        assert!(previous.is_none());
+        assert!(matches!(
+            RunLock::acquire_at(&path),
+            Err(SlagError::RunLocked { pid, ref run_id }) if pid == std::process::id() && *run_id == lock.owner.run_id
+        ));
+        drop(lock);
+        assert!(!path.exists());
+
+        // A run that died left its owner behind, but no lock on the file
+        let dead = LockOwner {
+            pid: 999_999_999,
+            run_id: "dead".into(),
+        };
+        std::fs::write(&path, dead.render()).unwrap();
+        let (lock, previous) = RunLock::acquire_at(&path).unwrap();
+        assert_eq!(previous, Some(dead));
+        assert_eq!(lock.owner.pid, std::process::id());
+    }
+
+    #[test]
+    fn lock_held_before_its_owner_is_written_still_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RUN_LOCK);
+
+        // Another run has created and locked the file but not written it yet
+        let starting = File::create(&path).unwrap();
+        starting.try_lock().unwrap();
+        assert!(matches!(
+            RunLock::acquire_at(&path),
+            Err(SlagError::RunLocked { pid: 0, .. })
+        ));
+        drop(starting);
+        assert!(RunLock::acquire_at(&path).is_ok());
+    }
+}
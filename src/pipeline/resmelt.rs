@@ -1,28 +1,33 @@
-use std::path::Path;
-
-use crate::crucible::Crucible;
+use crate::config::{paths, project_path, Phase};
+use crate::crucible::parse_ingot_lines;
 use crate::error::SlagError;
 use crate::flux;
-use crate::sexp::parser::parse_crucible;
 use crate::sexp::Ingot;
 use crate::smith::Smith;
 use crate::tui;
 
+/// Phase of an ingot's next resmelt: the first rewrites or splits it, the
+/// second reconsiders the approach
+pub fn phase_for(ingot: &Ingot) -> Phase {
+    if ingot.smelt >= 1 {
+        Phase::Reconsider
+    } else {
+        Phase::Resmelt
+    }
+}
+
 /// Attempt to re-smelt a cracked ingot.
 /// Analyzes failure and either rewrites, splits, or declares impossible.
-/// On success, updates the crucible in place (caller must save).
-pub async fn resmelt_ingot(
-    crucible: &mut Crucible,
-    ingot: &Ingot,
-    smith: &dyn Smith,
-) -> Result<(), SlagError> {
+/// On success, returns the replacement ingot(s); the caller applies them
+/// with `Crucible::replace`.
+pub async fn resmelt_ingot(ingot: &Ingot, smith: &dyn Smith) -> Result<Vec<Ingot>, SlagError> {
     if ingot.smelt >= 2 {
         println!("    \x1b[31m⚠\x1b[0m already reconsidered, truly cracked");
         return Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max));
     }
 
     if ingot.smelt >= 1 {
-        return reconsider_ingot(crucible, ingot, smith).await;
+        return reconsider_ingot(ingot, smith).await;
     }
 
     println!(
@@ -61,7 +66,7 @@ pub async fn resmelt_ingot(
     }
 
     // Try to extract ingot lines from response
-    let new_ingots = parse_crucible(&response);
+    let new_ingots = parse_ingot_lines(&response);
     if new_ingots.is_empty() {
         println!("    \x1b[31m✗\x1b[0m could not parse smelter output");
         return Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max));
@@ -81,15 +86,12 @@ pub async fn resmelt_ingot(
         );
     }
 
-    // Replace in crucible — the old ingot becomes the new one(s)
-    // Mark the old ingot position with the replacement(s)
-    crucible.replace(&ingot.id, new_ingots);
-
-    Ok(())
+    // The old ingot becomes the new one(s)
+    Ok(new_ingots)
 }
 
 fn gather_failure_logs(id: &str) -> String {
-    let log_dir = Path::new(crate::config::LOG_DIR);
+    let log_dir = project_path(&paths().log_dir);
     let mut logs = String::new();
 
     if let Ok(entries) = std::fs::read_dir(log_dir) {
@@ -115,12 +117,8 @@ fn gather_failure_logs(id: &str) -> String {
 }
 
 /// Reconsider a twice-failed ingot — surveyor-level re-analysis.
-/// Only touches this specific ingot in the crucible.
-async fn reconsider_ingot(
-    crucible: &mut Crucible,
-    ingot: &Ingot,
-    smith: &dyn Smith,
-) -> Result<(), SlagError> {
+/// Only produces replacements for this specific ingot.
+async fn reconsider_ingot(ingot: &Ingot, smith: &dyn Smith) -> Result<Vec<Ingot>, SlagError> {
     println!(
         "\n  \x1b[38;5;220m⚖\x1b[0m \x1b[1;37mRECONSIDERING [{}]\x1b[0m — rethinking approach...",
         ingot.id
@@ -153,7 +151,7 @@ async fn reconsider_ingot(
         return Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max));
     }
 
-    let new_ingots = parse_crucible(&response);
+    let new_ingots = parse_ingot_lines(&response);
     if new_ingots.is_empty() {
         println!("    \x1b[31m✗\x1b[0m could not parse reconsider output");
         return Err(SlagError::IngotCracked(ingot.id.clone(), ingot.max));
@@ -171,13 +169,11 @@ async fn reconsider_ingot(
         );
     }
 
-    crucible.replace(&ingot.id, new_ingots);
-
-    Ok(())
+    Ok(new_ingots)
 }
 
 fn log_to_file(label: &str, content: &str) {
     let ts = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let path = format!("{}/{ts}_{label}.log", crate::config::LOG_DIR);
+    let path = project_path(&paths().log_dir).join(format!("{ts}_{label}.log"));
     let _ = std::fs::write(&path, content);
 }
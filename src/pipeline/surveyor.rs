@@ -1,4 +1,4 @@
-use crate::config::{BLUEPRINT, MAX_ITERATE, ORE_FILE};
+use crate::config::{paths, project_path, settings};
 use crate::error::SlagError;
 use crate::flux;
 use crate::smith::{self, Smith};
@@ -8,7 +8,7 @@ use crate::tui;
 pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
     tui::header("SURVEYOR · deep analysis");
 
-    let ore = std::fs::read_to_string(ORE_FILE).map_err(|_| SlagError::NoOre)?;
+    let ore = std::fs::read_to_string(project_path(&paths().ore)).map_err(|_| SlagError::NoOre)?;
 
     let prompt = flux::surveyor_prompt(&ore);
     log_to_file("SURVEY_PROMPT", &prompt);
@@ -23,10 +23,14 @@ pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
     log_to_file("SURVEY_RAW", &raw);
 
     // Self-iterate if questions detected
-    let raw = smith::self_iterate(smith, raw, MAX_ITERATE).await?;
+    let raw = smith::self_iterate(smith, raw, settings().thresholds.max_iterate).await?;
 
-    std::fs::write(BLUEPRINT, &raw)?;
-    tui::status_line("█", tui::PURE, &format!("Blueprint: {BLUEPRINT}"));
+    std::fs::write(project_path(&paths().blueprint), &raw)?;
+    tui::status_line(
+        "█",
+        tui::PURE,
+        &format!("Blueprint: {}", project_path(&paths().blueprint).display()),
+    );
 
     // Show preview
     println!();
@@ -43,6 +47,6 @@ pub async fn run(smith: &dyn Smith) -> Result<(), SlagError> {
 
 fn log_to_file(label: &str, content: &str) {
     let ts = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let path = format!("{}/{ts}_{label}.log", crate::config::LOG_DIR);
+    let path = project_path(&paths().log_dir).join(format!("{ts}_{label}.log"));
     let _ = std::fs::write(&path, content);
 }
@@ -1,6 +1,6 @@
 use std::io::Write;
 
-use crate::config::LEDGER;
+use crate::config::{paths, project_path};
 use crate::sexp::Ingot;
 
 /// Structured progress entry for PROGRESS.md
@@ -16,7 +16,7 @@ pub fn append_entry(entry: &ProgressEntry) -> Result<(), std::io::Error> {
     let mut f = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(LEDGER)?;
+        .open(project_path(&paths().ledger))?;
 
     let ts = chrono::Local::now().format("%Y-%m-%d %H:%M");
     writeln!(f)?;
@@ -64,7 +64,7 @@ pub fn files_changed_since_last_commit() -> Vec<String> {
 
 /// Initialize the codebase patterns section in PROGRESS.md (Ralph-inspired)
 pub fn init_patterns_section() -> Result<(), std::io::Error> {
-    let ledger_path = std::path::Path::new(LEDGER);
+    let ledger_path = project_path(&paths().ledger);
     if ledger_path.exists() {
         let content = std::fs::read_to_string(ledger_path)?;
         if content.contains("## Codebase Patterns") {
@@ -75,7 +75,7 @@ pub fn init_patterns_section() -> Result<(), std::io::Error> {
     let mut f = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(LEDGER)?;
+        .open(project_path(&paths().ledger))?;
 
     writeln!(f, "\n## Codebase Patterns")?;
     writeln!(
@@ -91,7 +91,7 @@ pub fn append_pattern(pattern: &str) -> Result<(), std::io::Error> {
     let mut f = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(LEDGER)?;
+        .open(project_path(&paths().ledger))?;
 
     writeln!(f, "- {pattern}")?;
     Ok(())
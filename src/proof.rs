@@ -1,22 +1,83 @@
+use serde::Deserialize;
+
 use crate::error::SlagError;
 
+/// What a smith reports at the end of a strike, as JSON in a fenced block:
+/// `{"cmd": "cargo test", "files_touched": ["src/lib.rs"], "learnings": "...", "confidence": 0.8}`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Report {
+    /// Shell command that verifies the work
+    pub cmd: String,
+    #[serde(default)]
+    pub files_touched: Vec<String>,
+    /// Anything worth knowing for later ingots
+    pub learnings: Option<String>,
+    /// The smith's own estimate, 0.0 to 1.0
+    pub confidence: Option<f64>,
+}
+
+/// Extract the strike report from smith response text: the last fenced
+/// block that parses as a report, else a bare `CMD:` line.
+pub fn extract_report(response: &str) -> Option<Report> {
+    fenced_blocks(response)
+        .into_iter()
+        .rev()
+        .filter_map(|block| serde_json::from_str::<Report>(&block).ok())
+        .find(|report| !report.cmd.trim().is_empty())
+        .or_else(|| {
+            extract_cmd(response).map(|cmd| Report {
+                cmd,
+                ..Report::default()
+            })
+        })
+}
+
+/// Bodies of the ``` fenced blocks in a response
+fn fenced_blocks(response: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut open: Option<String> = None;
+    for line in response.lines() {
+        let fence = line.trim_start().starts_with("```");
+        match &mut open {
+            Some(body) if fence => blocks.push(std::mem::take(body)),
+            Some(body) => {
+                body.push_str(line);
+                body.push('\n');
+                continue;
+            }
+            None if fence => {
+                open = Some(String::new());
+                continue;
+            }
+            None => continue,
+        }
+        open = None;
+    }
+    blocks
+}
+
 /// Extract `CMD: <command>` from smith response text.
-/// Takes the last CMD: line found (smith may output multiple).
+/// Takes the last CMD: line found (smith may output multiple). The line may
+/// be indented, quoted, bulleted or in backticks, and a line that only
+/// mentions `CMD:` mid-text is used when no line starts with it.
 pub fn extract_cmd(response: &str) -> Option<String> {
-    response
-        .lines()
-        .rev()
-        .find(|line| line.starts_with("CMD:"))
-        .map(|line| line.strip_prefix("CMD:").unwrap().trim().to_string())
+    let lines = || response.lines().rev();
+    lines()
+        .find_map(|line| {
+            line.trim_start_matches(|c: char| c.is_whitespace() || "`*->".contains(c))
+                .strip_prefix("CMD:")
+        })
+        .or_else(|| lines().find_map(|line| line.rsplit_once("CMD:").map(|(_, cmd)| cmd)))
+        .map(|cmd| {
+            cmd.trim_matches(|c: char| c.is_whitespace() || c == '`' || c == '*')
+                .to_string()
+        })
+        .filter(|cmd| !cmd.is_empty())
 }
 
 /// Run a shell command and return (success, output).
 pub async fn run_shell(cmd: &str) -> (bool, String) {
-    match tokio::process::Command::new("bash")
-        .args(["-c", cmd])
-        .output()
-        .await
-    {
+    match crate::shutdown::output(tokio::process::Command::new("bash").args(["-c", cmd])).await {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -89,6 +150,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extract_cmd_decorated() {
+        for response in [
+            "  CMD: cargo test\n",
+            "`CMD: cargo test`\n",
+            "- **CMD:** `cargo test`\n",
+            "Done. Verify with CMD: cargo test\n",
+        ] {
+            assert_eq!(
+                extract_cmd(response).as_deref(),
+                Some("cargo test"),
+                "{response}"
+            );
+        }
+        assert_eq!(extract_cmd("CMD:\n"), None);
+    }
+
+    #[test]
+    fn extract_report_from_json_block() {
+        let response = "Wrote the parser.\n\
+            ```json\n\
+            {\"cmd\": \"cargo test\", \"files_touched\": [\"src/lib.rs\"],\n\
+             \"learnings\": \"tests need --features x\", \"confidence\": 0.8}\n\
+            ```\n";
+        let report = extract_report(response).unwrap();
+        assert_eq!(report.cmd, "cargo test");
+        assert_eq!(report.files_touched, ["src/lib.rs"]);
+        assert_eq!(report.learnings.as_deref(), Some("tests need --features x"));
+        assert_eq!(report.confidence, Some(0.8));
+    }
+
+    #[test]
+    fn extract_report_falls_back_to_cmd() {
+        // Code blocks that are not reports are passed over
+        let response = "```json\n{\"name\": \"pkg\"}\n```\n```\nCMD: npm test\n```\n";
+        assert_eq!(
+            extract_report(response),
+            Some(Report {
+                cmd: "npm test".into(),
+                ..Report::default()
+            })
+        );
+        assert_eq!(extract_report("no command"), None);
+    }
+
     #[tokio::test]
     async fn run_shell_success() {
         let (ok, _) = run_shell("true").await;
@@ -0,0 +1,252 @@
+use std::ffi::OsString;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+use crate::config::{SandboxBackend, SandboxSettings};
+
+/// Sets up the namespaces for the `unshare` backend, as root in a user
+/// namespace of its own: private /tmp, the writable paths bound back in
+/// (through descriptors opened before /tmp was covered), every other mount
+/// made read-only, then back to the caller's uid to run the command.
+/// Arguments: uid gid writable... -- program args...
+const UNSHARE_SETUP: &str = r#"set -e
+uid=$1 gid=$2
+shift 2
+here=$(pwd -P)
+paths=() fds=()
+while [ "$1" != -- ]; do
+    exec {fd}<"$1"
+    paths+=("$1") fds+=("$fd")
+    shift
+done
+shift
+mount -t tmpfs tmpfs /tmp
+for i in "${!paths[@]}"; do
+    p=${paths[$i]} fd=${fds[$i]}
+    if [ -d "/proc/self/fd/$fd" ]; then
+        mkdir -p "$p"
+    else
+        mkdir -p "${p%/*}"
+        [ -e "$p" ] || : >"$p"
+    fi
+    mount --no-canonicalize --bind "/proc/self/fd/$fd" "$p"
+    exec {fd}<&-
+done
+while read -r _ m _; do
+    m=$(printf '%b' "$m")
+    case $m in /proc | /proc/* | /sys | /sys/* | /dev | /dev/* | /tmp | /tmp/*) continue ;; esac
+    for p in "${paths[@]}"; do [ "$m" = "$p" ] && continue 2; done
+    mount -o remount,bind,ro "$m" 2>/dev/null || [ "$m" != / ]
+done </proc/self/mounts
+cd "$here"
+exec unshare --user --map-user="$uid" --map-group="$gid" -- "$@"
+"#;
+
+/// Where a strike's processes run: a read-only root with a private /tmp,
+/// writable only where listed, and offline unless `network`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    pub backend: SandboxBackend,
+    pub network: bool,
+    pub writable: Vec<PathBuf>,
+}
+
+impl Sandbox {
+    /// The sandbox for ingots of `skill`, writable in `[sandbox] writable`;
+    /// `None` when sandboxing is off for the skill
+    pub fn for_skill(settings: &SandboxSettings, skill: &str) -> Option<Self> {
+        let custom = settings.skills.get(skill).cloned().unwrap_or_default();
+        let backend = custom.backend.unwrap_or(settings.backend);
+        if backend == SandboxBackend::Off {
+            return None;
+        }
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let writable = settings
+            .writable
+            .iter()
+            .filter_map(|path| match (path.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => Some(home.join(rest)),
+                (Some(_), None) => None,
+                (None, _) => Some(PathBuf::from(path)),
+            })
+            .collect();
+        Some(Self {
+            backend,
+            network: custom.network.unwrap_or(settings.network),
+            writable,
+        })
+    }
+
+    /// Also allow writes under `dir`
+    pub fn writable(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.writable.push(dir.into());
+        self
+    }
+
+    /// Keep the network, whatever the settings say
+    pub fn online(mut self) -> Self {
+        self.network = true;
+        self
+    }
+
+    /// A command that runs `program` in the sandbox; add its arguments,
+    /// working directory and pipes as for the program itself
+    pub fn command(&self, program: impl Into<OsString>) -> Command {
+        let (wrapper, args) = self.wrapper();
+        let mut cmd = Command::new(wrapper);
+        cmd.args(args).arg(program.into());
+        cmd
+    }
+
+    /// The sandboxing program and its arguments, up to the wrapped program
+    fn wrapper(&self) -> (&'static str, Vec<OsString>) {
+        // Binding a missing path fails, so only existing ones are passed
+        let writable: Vec<PathBuf> = self
+            .writable
+            .iter()
+            .filter_map(|p| std::fs::canonicalize(p).ok())
+            .collect();
+        let mut args: Vec<OsString> = Vec::new();
+        match self.backend {
+            SandboxBackend::Off | SandboxBackend::Bwrap => {
+                for arg in ["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"] {
+                    args.push(arg.into());
+                }
+                for arg in ["--tmpfs", "/tmp", "--unshare-pid", "--die-with-parent"] {
+                    args.push(arg.into());
+                }
+                for path in &writable {
+                    args.extend(["--bind".into(), path.into(), path.into()]);
+                }
+                if !self.network {
+                    args.push("--unshare-net".into());
+                }
+                args.push("--".into());
+                ("bwrap", args)
+            }
+            SandboxBackend::Unshare => {
+                for arg in ["--user", "--map-root-user", "--mount"] {
+                    args.push(arg.into());
+                }
+                if !self.network {
+                    args.push("--net".into());
+                }
+                let (uid, gid) = owner(Path::new("/proc/self"));
+                args.extend(["--".into(), "bash".into(), "-c".into()]);
+                args.extend([UNSHARE_SETUP.into(), "slag-sandbox".into()]);
+                args.extend([uid.to_string().into(), gid.to_string().into()]);
+                args.extend(writable.into_iter().map(OsString::from));
+                args.push("--".into());
+                ("unshare", args)
+            }
+        }
+    }
+}
+
+/// `bash -c cmd`, in `sandbox` when there is one
+pub fn shell(cmd: &str, sandbox: Option<&Sandbox>) -> Command {
+    let mut shell = match sandbox {
+        Some(sandbox) => sandbox.command("bash"),
+        None => Command::new("bash"),
+    };
+    shell.args(["-c", cmd]);
+    shell
+}
+
+/// uid and gid owning `path`; /proc/self belongs to this process's user
+fn owner(path: &Path) -> (u32, u32) {
+    std::fs::metadata(path)
+        .map(|m| (m.uid(), m.gid()))
+        .unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(backend: SandboxBackend) -> SandboxSettings {
+        SandboxSettings {
+            backend,
+            writable: vec!["/nonexistent-slag-path".into()],
+            ..SandboxSettings::default()
+        }
+    }
+
+    #[test]
+    fn skills_override_backend_and_network() {
+        assert_eq!(
+            Sandbox::for_skill(&settings(SandboxBackend::Off), "default"),
+            None
+        );
+
+        let mut s = settings(SandboxBackend::Bwrap);
+        let default = Sandbox::for_skill(&s, "default").unwrap();
+        assert!(!default.network);
+        assert!(Sandbox::for_skill(&s, "web").unwrap().network);
+
+        s.skills.get_mut("web").unwrap().backend = Some(SandboxBackend::Off);
+        assert_eq!(Sandbox::for_skill(&s, "web"), None);
+    }
+
+    #[test]
+    fn bwrap_binds_only_existing_writable_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox::for_skill(&settings(SandboxBackend::Bwrap), "default")
+            .unwrap()
+            .writable(dir.path());
+        let (program, args) = sandbox.wrapper();
+        assert_eq!(program, "bwrap");
+
+        let args: Vec<String> = args
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        let real = std::fs::canonicalize(dir.path()).unwrap();
+        let real = real.to_string_lossy();
+        assert!(args.windows(3).any(|w| w == ["--bind", &*real, &*real]));
+        assert!(!args.iter().any(|a| a.contains("nonexistent")));
+        assert_eq!(args[args.len() - 2..], ["--unshare-net", "--"]);
+    }
+
+    /// Needs unprivileged user namespaces; skipped where they are off
+    #[tokio::test]
+    async fn unshare_confines_writes() {
+        let probe = Command::new("unshare")
+            .args(["--user", "--map-root-user", "--mount", "true"])
+            .output()
+            .await;
+        if !probe.map(|o| o.status.success()).unwrap_or(false) {
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        // Outside /tmp, which the sandbox replaces rather than protects
+        let outside = tempfile::tempdir_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let sandbox = Sandbox::for_skill(&settings(SandboxBackend::Unshare), "default")
+            .unwrap()
+            .writable(dir.path());
+        // Offline means a network namespace with nothing but lo
+        let script = format!(
+            "touch ok && ls -A /tmp && ! touch {}/no 2>/dev/null && [ $(grep -c : /proc/net/dev) = 1 ]",
+            outside.path().display()
+        );
+        let out = shell(&script, Some(&sandbox))
+            .current_dir(dir.path())
+            .output()
+            .await
+            .unwrap();
+
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert!(dir.path().join("ok").exists());
+        assert!(!outside.path().join("no").exists());
+        // The private /tmp holds only the paths bound into it
+        let listed = String::from_utf8_lossy(&out.stdout);
+        assert_eq!(listed.lines().count(), 1, "{listed}");
+    }
+}
@@ -1,8 +1,25 @@
 pub mod parser;
+pub mod reader;
 pub mod writer;
 
 use std::fmt;
 
+/// A read or parse failure at a 1-based line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, col {}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Status {
     Ore,
@@ -56,12 +73,13 @@ impl Skill {
         }
     }
 
-    pub fn parse(s: &str) -> Self {
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
-            "web" | "frontend" | "ui" | "css" | "html" => Skill::Web,
-            "api" => Skill::Api,
-            "cli" => Skill::Cli,
-            _ => Skill::Default,
+            "web" | "frontend" | "ui" | "css" | "html" => Some(Skill::Web),
+            "api" => Some(Skill::Api),
+            "cli" => Some(Skill::Cli),
+            "default" => Some(Skill::Default),
+            _ => None,
         }
     }
 }
@@ -72,16 +90,26 @@ impl fmt::Display for Skill {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Extra field holding the id of the agent session that forges an ingot
+pub const SESSION_FIELD: &str = "session";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ingot {
     pub id: String,
     pub status: Status,
     pub solo: bool,
+    /// Ids of ingots that must be forged before this one may start.
+    /// Empty means "no explicit graph" and falls back to `solo` semantics.
+    pub deps: Vec<String>,
     pub grade: u8,
     pub skill: Skill,
     pub heat: u8,
     pub max: u8,
     pub smelt: u8,
+    /// Seconds a smith may run per heat; overrides `[timeouts] strike`
+    pub timeout: Option<u32>,
+    /// Candidates struck at once, each in its own worktree; overrides `[pipeline] fanout`
+    pub fanout: Option<u8>,
     pub proof: String,
     pub work: String,
     /// Preserve unknown fields for forward compatibility
@@ -90,10 +118,42 @@ pub struct Ingot {
 
 impl Ingot {
     pub fn is_complex(&self) -> bool {
-        self.grade >= crate::config::HIGH_GRADE
+        self.grade >= crate::config::settings().thresholds.high_grade
     }
 
     pub fn is_web(&self) -> bool {
         self.skill == Skill::Web
     }
+
+    /// Candidates to strike at once: `:fanout`, else `default` for complex
+    /// ingots, else one
+    pub fn fanout(&self, default: u8) -> u8 {
+        let n = match self.fanout {
+            Some(n) => n,
+            None if self.is_complex() => default,
+            None => 1,
+        };
+        n.max(1)
+    }
+
+    /// Whether this ingot declares an explicit `:deps` list
+    pub fn has_deps(&self) -> bool {
+        !self.deps.is_empty()
+    }
+
+    /// Value of an unknown field, e.g. `:session`
+    pub fn extra_field(&self, key: &str) -> Option<&str> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set or (with `None`) remove an unknown field
+    pub fn set_extra_field(&mut self, key: &str, value: Option<&str>) {
+        self.extra.retain(|(k, _)| k != key);
+        if let Some(value) = value {
+            self.extra.push((key.to_string(), value.to_string()));
+        }
+    }
 }
@@ -1,148 +1,275 @@
-use super::{Ingot, Skill, Status};
+use super::reader::{Datum, Reader, Value};
+use super::{Ingot, ParseError, Skill, Status};
 
 /// Known field names that map to typed struct fields
-const KNOWN_FIELDS: &[&str] = &[
-    "id", "status", "solo", "grade", "skill", "heat", "max", "smelt", "proof", "work",
+pub const KNOWN_FIELDS: &[&str] = &[
+    "id", "status", "solo", "deps", "grade", "skill", "heat", "max", "smelt", "timeout", "fanout",
+    "proof", "work",
 ];
 
-/// Parse a single s-expression line into an Ingot.
+/// An ingot found in crucible content, with the line its form starts on
+#[derive(Debug, Clone)]
+pub struct ScannedIngot {
+    pub ingot: Ingot,
+    pub line: usize,
+}
+
+/// Everything found in crucible content: ingots, the remaining
+/// (header/prose) lines, any ingot forms that failed to parse, and fields
+/// that were read leniently (e.g. an unknown `:skill`, taken as default)
+#[derive(Debug, Default)]
+pub struct CrucibleScan {
+    pub ingots: Vec<ScannedIngot>,
+    pub header_lines: Vec<String>,
+    pub errors: Vec<ParseError>,
+    pub warnings: Vec<ParseError>,
+}
+
+/// Parse a single ingot s-expression (it may span several lines).
 ///
 /// Format: `(ingot :key value :key "quoted value" ...)`
-/// - Unquoted values end at space or `)`
-/// - Quoted values are delimited by `"`
+/// - Values may be strings, symbols, numbers or lists, e.g. `:deps ("i1" "i3")`
+/// - `;` comments are allowed anywhere outside strings
 /// - Unknown fields are preserved in `extra`
-pub fn parse_ingot(line: &str) -> Option<Ingot> {
-    let line = line.trim();
-    if !line.starts_with("(ingot ") {
-        return None;
-    }
-
-    // Strip outer parens
-    let inner = &line[7..line.len() - if line.ends_with(')') { 1 } else { 0 }];
+pub fn parse_ingot(src: &str) -> Option<Ingot> {
+    let datum = Reader::new(src).read().ok()??;
+    ingot_from_datum(&datum).ok()
+}
 
-    let fields = parse_fields(inner);
+/// Build an Ingot from a read `(ingot ...)` form, reporting the position of
+/// the first bad field.
+pub fn ingot_from_datum(datum: &Datum) -> Result<Ingot, ParseError> {
+    read_ingot(datum, &mut Vec::new())
+}
 
-    let get = |key: &str| -> Option<String> {
-        fields
-            .iter()
-            .find(|(k, _)| k == key)
-            .map(|(_, v)| v.clone())
+/// `ingot_from_datum`, noting fields it had to read leniently in `warnings`
+fn read_ingot(datum: &Datum, warnings: &mut Vec<ParseError>) -> Result<Ingot, ParseError> {
+    let items = match &datum.value {
+        Value::List(items) => items,
+        other => return Err(datum.error(format!("expected (ingot ...), found {}", other.kind()))),
     };
+    match items.first().map(|d| &d.value) {
+        Some(Value::Symbol(head)) if head == "ingot" => {}
+        _ => return Err(datum.error("expected (ingot ...)")),
+    }
 
-    let id = get("id")?;
-    let status = Status::parse(&get("status").unwrap_or_else(|| "ore".into()))?;
-    let solo = get("solo").map(|s| s == "t").unwrap_or(true);
-    let grade = get("grade").and_then(|s| s.parse().ok()).unwrap_or(1);
-    let skill_str = get("skill").unwrap_or_else(|| "default".into());
-    let skill = Skill::parse(&skill_str);
-    let heat = get("heat").and_then(|s| s.parse().ok()).unwrap_or(0);
-    let max = get("max").and_then(|s| s.parse().ok()).unwrap_or(5);
-    let smelt = get("smelt").and_then(|s| s.parse().ok()).unwrap_or(0);
-    let proof = get("proof").unwrap_or_else(|| "true".into());
-    let work = get("work").unwrap_or_default();
+    // Collect :key value pairs; the first occurrence of a key wins
+    let mut fields: Vec<(&str, &Datum)> = Vec::new();
+    let mut rest = items[1..].iter();
+    while let Some(key) = rest.next() {
+        let name = match &key.value {
+            Value::Keyword(name) => name.as_str(),
+            other => return Err(key.error(format!("expected :keyword, found {}", other.kind()))),
+        };
+        let value = rest
+            .next()
+            .ok_or_else(|| key.error(format!("missing value for :{name}")))?;
+        if !fields.iter().any(|(k, _)| *k == name) {
+            fields.push((name, value));
+        }
+    }
+    let get = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+    let id = match get("id") {
+        Some(d) => text(d, "id")?.to_string(),
+        None => return Err(datum.error("ingot has no :id")),
+    };
+    let status = match get("status") {
+        Some(d) => {
+            let s = text(d, "status")?;
+            Status::parse(s).ok_or_else(|| d.error(format!("unknown status '{s}'")))?
+        }
+        None => Status::Ore,
+    };
+    let solo = match get("solo") {
+        Some(d) => flag(d)?,
+        None => true,
+    };
+    let deps = match get("deps") {
+        Some(d) => list_of_text(d)?,
+        None => Vec::new(),
+    };
+    let grade = number(get("grade"), 1)?;
+    let skill = match get("skill") {
+        Some(d) => {
+            let s = text(d, "skill")?;
+            // Plans may name skills slag has no tools for; they forge as default
+            Skill::parse(s).unwrap_or_else(|| {
+                warnings.push(d.error(format!("unknown skill '{s}', forged as default")));
+                Skill::Default
+            })
+        }
+        None => Skill::Default,
+    };
+    let heat = number(get("heat"), 0)?;
+    let max = number(get("max"), 5)?;
+    let smelt = number(get("smelt"), 0)?;
+    let timeout = get("timeout").map(seconds).transpose()?;
+    let fanout = get("fanout").map(|d| number(Some(d), 1)).transpose()?;
+    let proof = match get("proof") {
+        Some(d) => text(d, "proof")?.to_string(),
+        None => "true".into(),
+    };
+    let work = match get("work") {
+        Some(d) => text(d, "work")?.to_string(),
+        None => String::new(),
+    };
 
     let extra: Vec<(String, String)> = fields
-        .into_iter()
-        .filter(|(k, _)| !KNOWN_FIELDS.contains(&k.as_str()))
+        .iter()
+        .filter(|(k, _)| !KNOWN_FIELDS.contains(k))
+        .map(|(k, v)| (k.to_string(), extra_text(v)))
         .collect();
 
-    Some(Ingot {
+    Ok(Ingot {
         id,
         status,
         solo,
+        deps,
         grade,
         skill,
         heat,
         max,
         smelt,
+        timeout,
+        fanout,
         proof,
         work,
         extra,
     })
 }
 
-/// Parse `:key value` pairs from the inner content of an s-expression.
-fn parse_fields(s: &str) -> Vec<(String, String)> {
-    let mut fields = Vec::new();
-    let chars: Vec<char> = s.chars().collect();
-    let len = chars.len();
-    let mut i = 0;
-
-    while i < len {
-        // Skip whitespace
-        while i < len && chars[i].is_whitespace() {
-            i += 1;
-        }
-        if i >= len {
-            break;
-        }
+fn text<'a>(d: &'a Datum, field: &str) -> Result<&'a str, ParseError> {
+    d.as_text().ok_or_else(|| {
+        d.error(format!(
+            ":{field} expects a string, found {}",
+            d.value.kind()
+        ))
+    })
+}
 
-        // Expect ':'
-        if chars[i] != ':' {
-            i += 1;
-            continue;
-        }
-        i += 1; // skip ':'
+fn flag(d: &Datum) -> Result<bool, ParseError> {
+    match &d.value {
+        Value::Symbol(s) if s == "t" || s == "true" => Ok(true),
+        Value::Symbol(s) if s == "nil" || s == "false" => Ok(false),
+        Value::List(items) if items.is_empty() => Ok(false),
+        other => Err(d.error(format!(":solo expects t or nil, found {other}"))),
+    }
+}
 
-        // Read key
-        let key_start = i;
-        while i < len && !chars[i].is_whitespace() {
-            i += 1;
-        }
-        let key: String = chars[key_start..i].iter().collect();
+fn number(d: Option<&Datum>, default: u8) -> Result<u8, ParseError> {
+    let Some(d) = d else { return Ok(default) };
+    let n = match &d.value {
+        Value::Integer(n) => *n,
+        Value::Str(s) | Value::Symbol(s) => s
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| d.error(format!("expected a number, found '{s}'")))?,
+        other => return Err(d.error(format!("expected a number, found {}", other.kind()))),
+    };
+    u8::try_from(n).map_err(|_| d.error(format!("number {n} out of range 0-255")))
+}
 
-        // Skip whitespace before value
-        while i < len && chars[i].is_whitespace() {
-            i += 1;
-        }
-        if i >= len {
-            fields.push((key, String::new()));
-            break;
-        }
+fn seconds(d: &Datum) -> Result<u32, ParseError> {
+    let n = match &d.value {
+        Value::Integer(n) => *n,
+        other => return Err(d.error(format!(":timeout expects seconds, found {}", other.kind()))),
+    };
+    u32::try_from(n).map_err(|_| d.error(format!(":timeout {n} out of range")))
+}
+
+fn list_of_text(d: &Datum) -> Result<Vec<String>, ParseError> {
+    match &d.value {
+        Value::List(items) => items
+            .iter()
+            .map(|item| text(item, "deps").map(str::to_string))
+            .collect(),
+        other => Err(d.error(format!(":deps expects a list, found {}", other.kind()))),
+    }
+}
 
-        // Read value (quoted or unquoted)
-        let value = if chars[i] == '"' {
-            i += 1; // skip opening quote
-            let val_start = i;
-            while i < len && chars[i] != '"' {
-                if chars[i] == '\\' && i + 1 < len {
-                    i += 2; // skip escaped char
-                } else {
-                    i += 1;
+/// Flatten an unknown field's value to the string kept in `extra`
+fn extra_text(d: &Datum) -> String {
+    match &d.value {
+        Value::Str(s) | Value::Symbol(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Find every `(ingot ...)` form in crucible content. A form starts at the
+/// beginning of a line (after indentation) or right after another form, and
+/// may span several lines; everything else, including text after a form on
+/// its line, is kept as header lines. Bad
+/// forms are reported in `errors` with their line and column and the scan
+/// continues after them.
+pub fn scan_crucible(content: &str) -> CrucibleScan {
+    let mut scan = CrucibleScan::default();
+
+    // Where to look next, and the line it is on
+    let mut pos = 0;
+    let mut line_start = 0;
+    let mut line_no = 1;
+    while pos < content.len() {
+        let line_end = content[pos..]
+            .find('\n')
+            .map_or(content.len(), |i| pos + i + 1);
+        let here = content[pos..line_end].trim_end_matches(['\n', '\r']);
+        let indent = here.len() - here.trim_start().len();
+        let rest = &here[indent..];
+        let starts_ingot = rest.strip_prefix("(ingot").is_some_and(|r| {
+            r.is_empty() || r.starts_with(|c: char| c.is_whitespace() || c == ')')
+        });
+
+        if starts_ingot {
+            let start = pos + indent;
+            let col = content[line_start..start].chars().count() + 1;
+            let mut reader = Reader::at(content, start, line_no, col);
+            match reader.read() {
+                Ok(Some(datum)) => {
+                    match read_ingot(&datum, &mut scan.warnings) {
+                        Ok(ingot) => scan.ingots.push(ScannedIngot {
+                            ingot,
+                            line: line_no,
+                        }),
+                        Err(e) => scan.errors.push(e),
+                    }
+                    // Resume right after the form: another may follow on its line
+                    pos = reader.pos();
+                    line_no = reader.line();
+                    line_start = content[..pos].rfind('\n').map_or(0, |i| i + 1);
+                    continue;
                 }
+                Ok(None) => {}
+                // Unreadable form: report it and move on to the next line
+                Err(e) => scan.errors.push(e),
             }
-            let val: String = chars[val_start..i].iter().collect();
-            if i < len {
-                i += 1; // skip closing quote
-            }
-            val
-        } else {
-            let val_start = i;
-            while i < len && !chars[i].is_whitespace() && chars[i] != ')' {
-                i += 1;
-            }
-            chars[val_start..i].iter().collect()
-        };
+        } else if pos == line_start {
+            scan.header_lines.push(
+                content[line_start..line_end]
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            );
+        } else if !rest.is_empty() {
+            // Text after a form on its line, e.g. a comment, is kept too
+            scan.header_lines.push(rest.to_string());
+        }
 
-        fields.push((key, value));
+        pos = line_end;
+        line_start = line_end;
+        line_no += 1;
     }
 
-    fields
+    scan
 }
 
-/// Parse all ingot lines from a crucible file's content.
-pub fn parse_crucible(content: &str) -> Vec<Ingot> {
-    content
-        .lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            if trimmed.starts_with("(ingot ") {
-                parse_ingot(trimmed)
-            } else {
-                None
-            }
-        })
-        .collect()
+/// Parse all ingots from a crucible file's content.
+/// Fails on the first malformed ingot, with its line and column.
+pub fn parse_crucible(content: &str) -> Result<Vec<Ingot>, ParseError> {
+    let scan = scan_crucible(content);
+    if let Some(e) = scan.errors.into_iter().next() {
+        return Err(e);
+    }
+    Ok(scan.ingots.into_iter().map(|s| s.ingot).collect())
 }
 
 #[cfg(test)]
@@ -207,7 +334,7 @@ mod tests {
 (ingot :id "i1" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "First")
 (ingot :id "i2" :status forged :solo nil :grade 2 :heat 3 :max 5 :proof "npm test" :work "Second")
 "#;
-        let ingots = parse_crucible(content);
+        let ingots = parse_crucible(content).unwrap();
         assert_eq!(ingots.len(), 2);
         assert_eq!(ingots[0].id, "i1");
         assert_eq!(ingots[1].id, "i2");
@@ -240,11 +367,164 @@ mod tests {
 (ingot :id "i2" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "test -f slag/index.html" :work "Verify HTML entry point exists")
 (ingot :id "i6" :status ore :solo nil :grade 3 :heat 0 :max 8 :proof "curl -s https://slag.dev | grep -q 'slag orchestrator'" :work "Deploy to Cloudflare Pages and verify live")
 "#;
-        let ingots = parse_crucible(content);
+        let ingots = parse_crucible(content).unwrap();
         assert_eq!(ingots.len(), 3);
         assert_eq!(ingots[0].proof, "test -f slag/wrangler.toml");
         assert_eq!(ingots[2].grade, 3);
         assert_eq!(ingots[2].max, 8);
         assert!(!ingots[2].solo);
     }
+
+    #[test]
+    fn parse_deps_list() {
+        let line = r#"(ingot :id "i4" :status ore :solo nil :deps ("i1" "i3") :grade 2 :heat 0 :max 5 :proof "true" :work "Wire it up")"#;
+        let ingot = parse_ingot(line).unwrap();
+        assert_eq!(ingot.deps, vec!["i1".to_string(), "i3".to_string()]);
+        assert_eq!(ingot.grade, 2);
+        assert!(ingot.extra.is_empty());
+    }
+
+    #[test]
+    fn parse_without_deps_is_empty() {
+        let line = r#"(ingot :id "i1" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "x")"#;
+        let ingot = parse_ingot(line).unwrap();
+        assert!(ingot.deps.is_empty());
+        assert!(!ingot.has_deps());
+    }
+
+    #[test]
+    fn roundtrip_deps() {
+        let line = r#"(ingot :id "i4" :status ore :solo nil :deps ("i1" "i3") :grade 2 :heat 0 :max 5 :proof "true" :work "Wire it up")"#;
+        let ingot = parse_ingot(line).unwrap();
+        let written = super::super::writer::write_ingot(&ingot);
+        let reparsed = parse_ingot(&written).unwrap();
+        assert_eq!(reparsed.deps, ingot.deps);
+    }
+
+    #[test]
+    fn roundtrip_timeout() {
+        let line = r#"(ingot :id "i1" :grade 1 :timeout 900 :proof "true" :work "Slow")"#;
+        let ingot = parse_ingot(line).unwrap();
+        assert_eq!(ingot.timeout, Some(900));
+        let written = super::super::writer::write_ingot(&ingot);
+        assert!(written.contains(" :timeout 900 "));
+        assert_eq!(parse_ingot(&written).unwrap().timeout, Some(900));
+
+        let plain = parse_ingot(r#"(ingot :id "i2" :work "x")"#).unwrap();
+        assert_eq!(plain.timeout, None);
+        assert!(!super::super::writer::write_ingot(&plain).contains(":timeout"));
+        assert!(parse_crucible(r#"(ingot :id "i3" :timeout "soon")"#).is_err());
+    }
+
+    #[test]
+    fn roundtrip_fanout() {
+        let line = r#"(ingot :id "i1" :grade 4 :fanout 3 :proof "true" :work "Hard")"#;
+        let ingot = parse_ingot(line).unwrap();
+        assert_eq!(ingot.fanout, Some(3));
+        let written = super::super::writer::write_ingot(&ingot);
+        assert!(written.contains(" :fanout 3 "));
+        assert_eq!(parse_ingot(&written).unwrap(), ingot);
+        assert!(!written.contains(":timeout"));
+    }
+
+    #[test]
+    fn parse_multiline_ingot_with_comments() {
+        let content = r#";; CRUCIBLE
+(ingot :id "i1" :status ore :solo t :grade 2
+       ; the founder wrapped this one
+       :proof "test -f index.html"
+       :work "Create the HTML structure,
+then style it")
+(ingot :id "i2" :status ore :solo t :grade 1 :heat 0 :max 5 :proof "true" :work "Next")
+"#;
+        let scan = scan_crucible(content);
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+        assert_eq!(scan.ingots.len(), 2);
+        assert_eq!(scan.ingots[0].line, 2);
+        assert_eq!(scan.ingots[0].ingot.grade, 2);
+        assert_eq!(
+            scan.ingots[0].ingot.work,
+            "Create the HTML structure,\nthen style it"
+        );
+        assert_eq!(scan.ingots[1].line, 7);
+        assert_eq!(scan.header_lines, vec![";; CRUCIBLE".to_string()]);
+    }
+
+    #[test]
+    fn parse_crucible_reports_position_of_bad_status() {
+        let content = r#";; CRUCIBLE
+(ingot :id "i1" :status ore :proof "true" :work "ok")
+(ingot :id "i2" :status mol10 :proof "true" :work "typo")
+"#;
+        let err = parse_crucible(content).unwrap_err();
+        assert_eq!((err.line, err.col), (3, 25));
+        assert!(err.message.contains("unknown status 'mol10'"));
+    }
+
+    #[test]
+    fn scan_reads_forms_sharing_a_line() {
+        let content = r#";; CRUCIBLE
+(ingot :id "i1" :work "one") (ingot :id "i2" :work "two
+continued")(ingot :id "i3" :work "three")
+(ingot :id "i4" :work "four")
+"#;
+        let scan = scan_crucible(content);
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+        let found: Vec<(&str, usize)> = scan
+            .ingots
+            .iter()
+            .map(|s| (s.ingot.id.as_str(), s.line))
+            .collect();
+        assert_eq!(found, [("i1", 2), ("i2", 2), ("i3", 3), ("i4", 4)]);
+        assert_eq!(scan.header_lines, [";; CRUCIBLE"]);
+    }
+
+    #[test]
+    fn scan_keeps_text_after_a_form() {
+        let content = "(ingot :id \"i1\" :work \"one\") ; keep until v2\n(ingot :id \"i2\" :work \"two\")   \n";
+        let scan = scan_crucible(content);
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+        assert_eq!(scan.ingots.len(), 2);
+        assert_eq!(scan.header_lines, ["; keep until v2"]);
+    }
+
+    #[test]
+    fn unknown_skills_read_as_default_with_a_warning() {
+        let alias = parse_crucible(r#"(ingot :id "i1" :skill frontend :work "ok")"#).unwrap();
+        assert_eq!(alias[0].skill, Skill::Web);
+
+        let scan = scan_crucible(r#"(ingot :id "i1" :skill docker :work "ok")"#);
+        assert!(scan.errors.is_empty());
+        assert_eq!(scan.ingots[0].ingot.skill, Skill::Default);
+        assert_eq!(scan.warnings.len(), 1);
+        assert!(scan.warnings[0].message.contains("unknown skill 'docker'"));
+    }
+
+    #[test]
+    fn scan_continues_after_unclosed_ingot() {
+        let content = r#"(ingot :id "i1" :work "never closed"
+(ingot :id "i2" :work "fine")
+"#;
+        let scan = scan_crucible(content);
+        assert_eq!(scan.errors.len(), 1);
+        assert_eq!((scan.errors[0].line, scan.errors[0].col), (1, 1));
+        assert_eq!(scan.ingots.len(), 1);
+        assert_eq!(scan.ingots[0].ingot.id, "i2");
+    }
+
+    #[test]
+    fn scan_ignores_prose_around_ingots() {
+        let content = "Here is the plan:\n```lisp\n(ingot :id \"i1\" :work \"x\")\n```\n";
+        let scan = scan_crucible(content);
+        assert!(scan.errors.is_empty());
+        assert_eq!(scan.ingots.len(), 1);
+        assert_eq!(scan.header_lines.len(), 3);
+    }
+
+    #[test]
+    fn parse_rejects_missing_value() {
+        let content = r#"(ingot :id "i1" :grade)"#;
+        let err = parse_crucible(content).unwrap_err();
+        assert!(err.message.contains("missing value for :grade"));
+    }
 }
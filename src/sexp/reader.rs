@@ -0,0 +1,362 @@
+use std::fmt;
+
+use super::ParseError;
+
+/// A parsed S-expression value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    List(Vec<Datum>),
+    /// Bare word such as `ingot`, `ore` or `t`
+    Symbol(String),
+    /// `:key`, stored without the leading colon
+    Keyword(String),
+    Integer(i64),
+    Float(f64),
+    /// String literal with escapes already resolved
+    Str(String),
+}
+
+/// A value together with the 1-based position where it starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datum {
+    pub value: Value,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Datum {
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            col: self.col,
+            message: message.into(),
+        }
+    }
+
+    /// Text of a string or symbol, the forms ids and commands are written in
+    pub fn as_text(&self) -> Option<&str> {
+        match &self.value {
+            Value::Str(s) | Value::Symbol(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    /// Short name of the value's kind, for error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::List(_) => "list",
+            Value::Symbol(_) => "symbol",
+            Value::Keyword(_) => "keyword",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "number",
+            Value::Str(_) => "string",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::List(items) => {
+                f.write_str("(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{}", item.value)?;
+                }
+                f.write_str(")")
+            }
+            Value::Symbol(s) => f.write_str(s),
+            Value::Keyword(k) => write!(f, ":{k}"),
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::Float(n) => write!(f, "{n}"),
+            Value::Str(s) => f.write_str(&super::writer::quote(s)),
+        }
+    }
+}
+
+/// Streaming S-expression reader with line/column tracking.
+///
+/// Handles nested lists, symbols, `:keywords`, integers and floats,
+/// strings with `\"`, `\\`, `\n`, `\t`, `\r` escapes, and `;` comments
+/// anywhere outside strings. Unknown escapes such as `\.` are kept verbatim
+/// so shell regexes in older plans read back unchanged.
+pub struct Reader<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    /// Start reading at a byte offset whose line/column are already known
+    pub fn at(src: &'a str, pos: usize, line: usize, col: usize) -> Self {
+        Self {
+            src,
+            pos,
+            line,
+            col,
+        }
+    }
+
+    /// Current 1-based line
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Current byte offset, just past the last datum read
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Read the next datum, or `None` at end of input
+    pub fn read(&mut self) -> Result<Option<Datum>, ParseError> {
+        self.skip_trivia();
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        self.read_datum().map(Some)
+    }
+
+    fn read_datum(&mut self) -> Result<Datum, ParseError> {
+        self.skip_trivia();
+        let (line, col) = (self.line, self.col);
+        let value = match self.peek() {
+            None => return Err(self.error("unexpected end of input")),
+            Some('(') => {
+                self.bump();
+                let mut items = Vec::new();
+                loop {
+                    self.skip_trivia();
+                    match self.peek() {
+                        None => {
+                            return Err(ParseError {
+                                line,
+                                col,
+                                message: "unclosed list".into(),
+                            })
+                        }
+                        Some(')') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(_) => items.push(self.read_datum()?),
+                    }
+                }
+                Value::List(items)
+            }
+            Some(')') => return Err(self.error("unexpected ')'")),
+            Some('"') => Value::Str(self.read_string(line, col)?),
+            Some(_) => atom(self.read_token()),
+        };
+        Ok(Datum { value, line, col })
+    }
+
+    fn read_string(&mut self, line: usize, col: usize) -> Result<String, ParseError> {
+        self.bump(); // opening quote
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(ParseError {
+                        line,
+                        col,
+                        message: "unterminated string".into(),
+                    })
+                }
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some(other) => {
+                        out.push('\\');
+                        out.push(other);
+                    }
+                    None => out.push('\\'),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn read_token(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, '(' | ')' | '"' | ';') {
+                break;
+            }
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == ';' {
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.bump();
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            col: self.col,
+            message: message.into(),
+        }
+    }
+}
+
+/// Classify a bare token as keyword, number or symbol
+pub fn atom(token: &str) -> Value {
+    if let Some(key) = token.strip_prefix(':') {
+        return Value::Keyword(key.to_string());
+    }
+    if let Ok(n) = token.parse::<i64>() {
+        return Value::Integer(n);
+    }
+    if token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        if let Ok(n) = token.parse::<f64>() {
+            return Value::Float(n);
+        }
+    }
+    Value::Symbol(token.to_string())
+}
+
+/// Read every datum in the input
+pub fn read_all(src: &str) -> Result<Vec<Datum>, ParseError> {
+    let mut reader = Reader::new(src);
+    let mut out = Vec::new();
+    while let Some(datum) = reader.read()? {
+        out.push(datum);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_one(src: &str) -> Value {
+        read_all(src).unwrap().remove(0).value
+    }
+
+    #[test]
+    fn reads_atoms() {
+        assert_eq!(read_one("ore"), Value::Symbol("ore".into()));
+        assert_eq!(read_one(":grade"), Value::Keyword("grade".into()));
+        assert_eq!(read_one("42"), Value::Integer(42));
+        assert_eq!(read_one("-3"), Value::Integer(-3));
+        assert_eq!(read_one("0.5"), Value::Float(0.5));
+        assert_eq!(read_one("\"hi there\""), Value::Str("hi there".into()));
+    }
+
+    #[test]
+    fn reads_nested_lists() {
+        let v = read_one("(a (b \"c\") ())");
+        let Value::List(items) = v else {
+            panic!("expected list")
+        };
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[1].value, Value::List(inner) if inner.len() == 2));
+        assert_eq!(items[2].value, Value::List(vec![]));
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            read_one(r#""say \"hi\"\n\\ done""#),
+            Value::Str("say \"hi\"\n\\ done".into())
+        );
+        // Unknown escapes survive untouched
+        assert_eq!(read_one(r#""a\.b""#), Value::Str("a\\.b".into()));
+    }
+
+    #[test]
+    fn comments_anywhere() {
+        let data = read_all("; header\n(a ; trailing\n b) ; done\n").unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].value.to_string(), "(a b)");
+    }
+
+    #[test]
+    fn semicolon_inside_string_is_not_comment() {
+        assert_eq!(read_one("\"a; b\""), Value::Str("a; b".into()));
+    }
+
+    #[test]
+    fn tracks_positions() {
+        let data = read_all("\n  (a\n   b)").unwrap();
+        assert_eq!((data[0].line, data[0].col), (2, 3));
+        let Value::List(items) = &data[0].value else {
+            panic!("expected list")
+        };
+        assert_eq!((items[1].line, items[1].col), (3, 4));
+    }
+
+    #[test]
+    fn reports_unclosed_list() {
+        let err = read_all("(a\n (b c)").unwrap_err();
+        assert_eq!((err.line, err.col), (1, 1));
+        assert!(err.message.contains("unclosed"));
+    }
+
+    #[test]
+    fn reports_unterminated_string() {
+        let err = read_all("(a \"oops)").unwrap_err();
+        assert_eq!((err.line, err.col), (1, 4));
+    }
+
+    #[test]
+    fn reports_stray_paren() {
+        let err = read_all("a )").unwrap_err();
+        assert_eq!((err.line, err.col), (1, 3));
+    }
+
+    #[test]
+    fn display_roundtrips() {
+        let src = r#"(ingot :id "i1" :n 3 :deps ("a" b) :s "q\"uote")"#;
+        let printed = read_one(src).to_string();
+        assert_eq!(read_one(&printed), read_one(src));
+    }
+}
@@ -1,29 +1,49 @@
+use super::reader::{atom, Value};
 use super::Ingot;
 
 /// Serialize an Ingot back to its s-expression string representation.
+/// Strings are escaped so that `parse_ingot(&write_ingot(x)) == x`.
 pub fn write_ingot(ingot: &Ingot) -> String {
     let solo = if ingot.solo { "t" } else { "nil" };
+    // Only emit :deps when the ingot has an explicit graph, so legacy plans stay unchanged
+    let deps = if ingot.deps.is_empty() {
+        String::new()
+    } else {
+        let items: Vec<String> = ingot.deps.iter().map(|d| quote(d)).collect();
+        format!(" :deps ({})", items.join(" "))
+    };
+    let timeout = match ingot.timeout {
+        Some(secs) => format!(" :timeout {secs}"),
+        None => String::new(),
+    };
+    let fanout = match ingot.fanout {
+        Some(n) => format!(" :fanout {n}"),
+        None => String::new(),
+    };
     let mut s = format!(
-        "(ingot :id \"{}\" :status {} :solo {} :grade {} :skill {} :heat {} :max {} :smelt {} :proof \"{}\" :work \"{}\"",
-        ingot.id,
+        "(ingot :id {} :status {} :solo {}{} :grade {} :skill {} :heat {} :max {} :smelt {}{}{} :proof {} :work {}",
+        quote(&ingot.id),
         ingot.status,
         solo,
+        deps,
         ingot.grade,
         ingot.skill,
         ingot.heat,
         ingot.max,
         ingot.smelt,
-        ingot.proof,
-        ingot.work,
+        timeout,
+        fanout,
+        quote(&ingot.proof),
+        quote(&ingot.work),
     );
 
     // Append unknown extra fields for forward compatibility
     for (key, value) in &ingot.extra {
-        // If value looks like it needs quoting (contains spaces), quote it
-        if value.contains(' ') || value.contains('"') {
-            s.push_str(&format!(" :{key} \"{value}\""));
-        } else {
+        // Bare words stay bare; anything that would read back differently is quoted
+        if is_bare_symbol(value) {
             s.push_str(&format!(" :{key} {value}"));
+        } else {
+            s.push_str(&format!(" :{key} {}", quote(value)));
         }
     }
 
@@ -31,6 +51,34 @@ pub fn write_ingot(ingot: &Ingot) -> String {
     s
 }
 
+/// Quote a string literal, escaping `"`, `\` and control characters
+/// so the reader gets back exactly the same text.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Whether a value can be written unquoted and still read back as the same text
+fn is_bare_symbol(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"' | ';' | '\\'))
+        && atom(value) == Value::Symbol(value.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,11 +90,14 @@ mod tests {
             id: "i1".into(),
             status: Status::Ore,
             solo: true,
+            deps: vec![],
             grade: 2,
             skill: Skill::Web,
             heat: 0,
             max: 5,
             smelt: 0,
+            timeout: None,
+            fanout: None,
             proof: "test -f index.html".into(),
             work: "Create HTML structure".into(),
             extra: vec![],
@@ -67,11 +118,14 @@ mod tests {
             id: "i5".into(),
             status: Status::Cracked,
             solo: false,
+            deps: vec![],
             grade: 4,
             skill: Skill::Cli,
             heat: 6,
             max: 8,
             smelt: 1,
+            timeout: None,
+            fanout: None,
             proof: "npm test".into(),
             work: "Deploy app".into(),
             extra: vec![],
@@ -88,11 +142,14 @@ mod tests {
             id: "i1".into(),
             status: Status::Ore,
             solo: true,
+            deps: vec![],
             grade: 1,
             skill: Skill::Default,
             heat: 0,
             max: 5,
             smelt: 0,
+            timeout: None,
+            fanout: None,
             proof: "true".into(),
             work: "test".into(),
             extra: vec![("custom".into(), "hello".into())],
@@ -100,4 +157,99 @@ mod tests {
         let s = write_ingot(&ingot);
         assert!(s.contains(":custom hello"));
     }
+
+    #[test]
+    fn write_deps_only_when_present() {
+        let mut ingot = Ingot {
+            id: "i2".into(),
+            status: Status::Ore,
+            solo: false,
+            deps: vec![],
+            grade: 1,
+            skill: Skill::Default,
+            heat: 0,
+            max: 5,
+            smelt: 0,
+            timeout: None,
+            fanout: None,
+            proof: "true".into(),
+            work: "test".into(),
+            extra: vec![],
+        };
+        assert!(!write_ingot(&ingot).contains(":deps"));
+
+        ingot.deps = vec!["i1".into(), "i3".into()];
+        assert!(write_ingot(&ingot).contains(":solo nil :deps (\"i1\" \"i3\") :grade 1"));
+    }
+
+    fn tricky_ingot(text: &str) -> Ingot {
+        Ingot {
+            id: format!("i{}", text.len()),
+            status: Status::Molten,
+            solo: false,
+            deps: vec!["i1".into(), text.into()],
+            grade: 3,
+            skill: Skill::Api,
+            heat: 2,
+            max: 8,
+            smelt: 1,
+            timeout: None,
+            fanout: None,
+            proof: text.into(),
+            work: format!("Handle {text} carefully"),
+            extra: vec![
+                ("note".into(), text.into()),
+                ("plain".into(), "word".into()),
+            ],
+        }
+    }
+
+    #[test]
+    fn quote_escapes() {
+        assert_eq!(
+            quote(r#"grep -q "ok" out.txt"#),
+            r#""grep -q \"ok\" out.txt""#
+        );
+        assert_eq!(quote(r"a\b"), r#""a\\b""#);
+        assert_eq!(quote("two\nlines"), r#""two\nlines""#);
+    }
+
+    #[test]
+    fn roundtrip_tricky_strings() {
+        let samples = [
+            r#"grep -q "ok" out.txt"#,
+            r"grep -E 'v[0-9]+\.[0-9]+' VERSION",
+            r#"echo '{"status": "ok", "path": "C:\\tmp"}' | jq -e .status"#,
+            "trailing backslash \\",
+            "\\\"",
+            "multi\nline\twith\rcontrols",
+            "semi; colon (parens) and :keyword",
+            "",
+            "42",
+            "-1.5",
+            ":not-a-key",
+            "unicode ⚒ 🔥",
+        ];
+        for text in samples {
+            let ingot = tricky_ingot(text);
+            let written = write_ingot(&ingot);
+            let parsed = crate::sexp::parser::parse_ingot(&written)
+                .unwrap_or_else(|| panic!("failed to reparse {written}"));
+            assert_eq!(parsed, ingot, "roundtrip of {text:?} via {written}");
+        }
+    }
+
+    #[test]
+    fn extra_values_quoted_only_when_needed() {
+        let mut ingot = tricky_ingot("x");
+        ingot.extra = vec![
+            ("a".into(), "hello".into()),
+            ("b".into(), "007".into()),
+            ("c".into(), "say \"hi\"".into()),
+        ];
+        let s = write_ingot(&ingot);
+        assert!(s.contains(":a hello"));
+        assert!(s.contains(r#":b "007""#));
+        assert!(s.contains(r#":c "say \"hi\"""#));
+    }
 }
@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+use std::process::Output;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Set once on the first Ctrl-C
+static REQUESTED: OnceLock<watch::Sender<bool>> = OnceLock::new();
+/// How long a process group gets to exit on SIGTERM before SIGKILL
+const KILL_GRACE: Duration = Duration::from_secs(2);
+/// Number of live `GracefulScope`s; while non-zero the holder owns cleanup
+static SCOPES: AtomicUsize = AtomicUsize::new(0);
+/// Process groups of running children, killed on a forced exit
+static GROUPS: Mutex<Option<HashSet<u32>>> = Mutex::new(None);
+
+fn sender() -> &'static watch::Sender<bool> {
+    REQUESTED.get_or_init(|| watch::channel(false).0)
+}
+
+/// Install the Ctrl-C handler. The first press requests a graceful stop;
+/// the second kills every tracked child process group and exits at once.
+pub fn install() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        println!("\n\n  \x1b[38;5;208m⏹\x1b[0m stopping… \x1b[90m(Ctrl-C again to force)\x1b[0m");
+        request();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            kill_all_groups();
+            eprintln!("\n  \x1b[31m✗\x1b[0m forced exit\n");
+            std::process::exit(130);
+        }
+    });
+}
+
+/// Ask for a graceful stop, as the first Ctrl-C does
+pub fn request() {
+    sender().send_replace(true);
+}
+
+/// Withdraw a stop request once it has been handled, so a later run in the
+/// same process starts clean
+pub fn reset() {
+    sender().send_replace(false);
+}
+
+/// Whether a graceful stop was requested
+pub fn is_requested() -> bool {
+    *sender().borrow()
+}
+
+/// Resolves once a graceful stop is requested
+pub async fn requested() {
+    let mut rx = sender().subscribe();
+    let _ = rx.wait_for(|stop| *stop).await;
+}
+
+/// Resolves when a stop was requested and no `GracefulScope` is there to
+/// handle it, meaning the caller should abandon its work.
+pub async fn abandoned() {
+    requested().await;
+    if SCOPES.load(Ordering::SeqCst) > 0 {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Marks a region that reacts to `requested()` itself and cleans up
+/// before returning, so the caller must not cut it short.
+pub struct GracefulScope(());
+
+impl GracefulScope {
+    pub fn enter() -> Self {
+        SCOPES.fetch_add(1, Ordering::SeqCst);
+        GracefulScope(())
+    }
+}
+
+impl Drop for GracefulScope {
+    fn drop(&mut self) {
+        SCOPES.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A child running in its own process group. Dropping the guard before
+/// `finish` (e.g. because the task was cancelled) kills the whole group,
+/// including anything the child spawned.
+pub struct ChildGroup {
+    pgid: Option<u32>,
+}
+
+impl ChildGroup {
+    /// Track a child spawned with `process_group(0)` (its pid is the group id)
+    pub fn track(child: &tokio::process::Child) -> Self {
+        let pgid = child.id();
+        if let Some(id) = pgid {
+            GROUPS
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert_with(HashSet::new)
+                .insert(id);
+        }
+        ChildGroup { pgid }
+    }
+
+    /// The child exited normally; leave its group alone
+    pub fn finish(mut self) {
+        if let Some(id) = self.pgid.take() {
+            untrack(id);
+        }
+    }
+}
+
+impl Drop for ChildGroup {
+    fn drop(&mut self) {
+        if let Some(id) = self.pgid.take() {
+            // Stays tracked until the grace period is over, so a forced exit
+            // or `kill_stragglers` still reaches it
+            signal_group(id, "TERM");
+            std::thread::spawn(move || {
+                std::thread::sleep(KILL_GRACE);
+                signal_group(id, "KILL");
+                untrack(id);
+            });
+        }
+    }
+}
+
+/// Spawn a command in its own process group and collect its output.
+/// If the future is dropped first, the group is killed.
+pub async fn output(cmd: &mut tokio::process::Command) -> std::io::Result<Output> {
+    let child = cmd
+        .process_group(0)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let group = ChildGroup::track(&child);
+    let output = child.wait_with_output().await;
+    group.finish();
+    output
+}
+
+/// Before exiting: give signalled process groups the rest of their grace
+/// period, then SIGKILL whatever is left.
+pub fn kill_stragglers() {
+    let deadline = std::time::Instant::now() + KILL_GRACE;
+    loop {
+        let alive: Vec<u32> = tracked()
+            .into_iter()
+            .filter(|&pgid| signal_group(pgid, "0"))
+            .collect();
+        if alive.is_empty() {
+            return;
+        }
+        if std::time::Instant::now() >= deadline {
+            for pgid in alive {
+                signal_group(pgid, "KILL");
+            }
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn tracked() -> Vec<u32> {
+    GROUPS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|g| g.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+fn untrack(pgid: u32) {
+    if let Some(groups) = GROUPS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        groups.remove(&pgid);
+    }
+}
+
+/// `kill -SIGNAL -- -pgid`; true if the group existed
+fn signal_group(pgid: u32, signal: &str) -> bool {
+    std::process::Command::new("kill")
+        .args([&format!("-{signal}"), "--", &format!("-{pgid}")])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn kill_all_groups() {
+    let groups = GROUPS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .unwrap_or_default();
+    for pgid in groups {
+        signal_group(pgid, "KILL");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alive(pid: &str) -> bool {
+        std::process::Command::new("kill")
+            .args(["-0", pid])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    #[tokio::test]
+    async fn output_collects_like_std() {
+        let out = output(tokio::process::Command::new("bash").args(["-c", "echo hi"]))
+            .await
+            .unwrap();
+        assert!(out.status.success());
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "hi\n");
+    }
+
+    #[tokio::test]
+    async fn cancelling_kills_grandchildren() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+
+        let mut cmd = tokio::process::Command::new("bash");
+        cmd.args(["-c", &script]);
+        let task = tokio::spawn(async move { output(&mut cmd).await });
+
+        let mut grandchild = String::new();
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            grandchild = std::fs::read_to_string(&pid_file).unwrap_or_default();
+            if !grandchild.trim().is_empty() {
+                break;
+            }
+        }
+        let grandchild = grandchild.trim().to_string();
+        assert!(alive(&grandchild));
+
+        task.abort();
+        let _ = task.await;
+        for _ in 0..100 {
+            if !alive(&grandchild) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive(&grandchild));
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::session::Session;
+use super::usage::{Reported, Usage};
+use crate::error::SlagError;
+
+/// Adapter used when `[smith] adapter` is not set
+pub const DEFAULT_ADAPTER: &str = "claude";
+
+/// Tools granted to web-skill smiths, expanded into `{tools}` in `tools_args`
+pub const WEB_TOOLS: &[&str] = &["Bash", "Edit", "Read", "Write", "Playwright"];
+
+/// How a CLI agent receives the prompt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptVia {
+    /// Written to the agent's stdin
+    #[default]
+    Stdin,
+    /// Passed as the last argument, after `prompt_flag` if set
+    Arg,
+    /// Written to a temp file whose path is passed after `prompt_flag`
+    File,
+}
+
+/// How the final answer is pulled out of the agent's stdout
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Extract {
+    /// All of stdout
+    #[default]
+    Raw,
+    /// A string field (dotted path) of the last JSON object printed
+    Json(String),
+    /// Everything after the last line equal to the marker
+    After(String),
+    /// Claude's `--output-format stream-json` events: the answer is the
+    /// `result` event, tool calls are reported live
+    #[serde(rename = "stream-json")]
+    StreamJson,
+}
+
+/// One CLI coding agent (`[adapters.<name>]`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Adapter {
+    /// Program and base arguments
+    pub command: Vec<String>,
+    pub prompt: PromptVia,
+    pub prompt_flag: Option<String>,
+    /// Appended for plan mode (complex ingots, survey, found)
+    pub plan_args: Vec<String>,
+    /// Appended to grant tools; `{tools}` becomes the space-separated list.
+    /// Empty when the agent has no allow-list.
+    pub tools_args: Vec<String>,
+    /// Appended when a route picks a model; `{model}` becomes its name
+    pub model_args: Vec<String>,
+    /// Appended to start a session (`{session}` becomes its id) and to
+    /// continue one. Empty when the agent has no sessions.
+    pub session_args: Vec<String>,
+    pub resume_args: Vec<String>,
+    pub output: Extract,
+}
+
+/// Agents slag knows out of the box; `[adapters.<name>]` tweaks or adds to them
+pub fn builtin() -> BTreeMap<String, Adapter> {
+    let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    BTreeMap::from([
+        (
+            "claude".to_string(),
+            Adapter {
+                command: args(&[
+                    "claude",
+                    "--dangerously-skip-permissions",
+                    "--output-format",
+                    "json",
+                    "-p",
+                ]),
+                prompt: PromptVia::Stdin,
+                prompt_flag: None,
+                plan_args: args(&["--permission-mode", "plan"]),
+                tools_args: args(&["--allowedTools", "{tools}"]),
+                model_args: args(&["--model", "{model}"]),
+                session_args: args(&["--session-id", "{session}"]),
+                resume_args: args(&["--resume", "{session}"]),
+                output: Extract::Json("result".into()),
+            },
+        ),
+        (
+            "claude-stream".to_string(),
+            Adapter {
+                command: args(&[
+                    "claude",
+                    "--dangerously-skip-permissions",
+                    "--output-format",
+                    "stream-json",
+                    "--verbose",
+                    "-p",
+                ]),
+                prompt: PromptVia::Stdin,
+                prompt_flag: None,
+                plan_args: args(&["--permission-mode", "plan"]),
+                tools_args: args(&["--allowedTools", "{tools}"]),
+                model_args: args(&["--model", "{model}"]),
+                session_args: args(&["--session-id", "{session}"]),
+                resume_args: args(&["--resume", "{session}"]),
+                output: Extract::StreamJson,
+            },
+        ),
+        (
+            "codex".to_string(),
+            Adapter {
+                command: args(&["codex", "exec", "--full-auto"]),
+                prompt: PromptVia::Arg,
+                prompt_flag: None,
+                plan_args: args(&["--sandbox", "read-only"]),
+                tools_args: Vec::new(),
+                model_args: args(&["--model", "{model}"]),
+                session_args: Vec::new(),
+                resume_args: Vec::new(),
+                output: Extract::Raw,
+            },
+        ),
+        (
+            "aider".to_string(),
+            Adapter {
+                command: args(&["aider", "--yes-always", "--no-auto-commits", "--no-pretty"]),
+                prompt: PromptVia::File,
+                prompt_flag: Some("--message-file".into()),
+                plan_args: args(&["--chat-mode", "ask"]),
+                tools_args: Vec::new(),
+                model_args: args(&["--model", "{model}"]),
+                session_args: Vec::new(),
+                resume_args: Vec::new(),
+                output: Extract::Raw,
+            },
+        ),
+        (
+            "gemini".to_string(),
+            Adapter {
+                command: args(&["gemini", "--yolo"]),
+                prompt: PromptVia::Arg,
+                prompt_flag: Some("--prompt".into()),
+                plan_args: args(&["--approval-mode", "plan"]),
+                tools_args: args(&["--allowed-tools", "{tools}"]),
+                model_args: args(&["--model", "{model}"]),
+                session_args: Vec::new(),
+                resume_args: Vec::new(),
+                output: Extract::Raw,
+            },
+        ),
+    ])
+}
+
+/// A resolved CLI smith call: full argv plus how the adapter talks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub argv: Vec<String>,
+    pub prompt: PromptVia,
+    pub prompt_flag: Option<String>,
+    pub session_args: Vec<String>,
+    pub resume_args: Vec<String>,
+    pub output: Extract,
+}
+
+impl Invocation {
+    /// Call the adapter with this argv
+    pub fn new(adapter: &Adapter, argv: Vec<String>) -> Self {
+        Self {
+            argv,
+            prompt: adapter.prompt,
+            prompt_flag: adapter.prompt_flag.clone(),
+            session_args: adapter.session_args.clone(),
+            resume_args: adapter.resume_args.clone(),
+            output: adapter.output.clone(),
+        }
+    }
+
+    /// Append arguments, expanding `{tools}`
+    pub fn with_args(mut self, args: &[String], tools: &[&str]) -> Self {
+        let tools = tools.join(" ");
+        self.argv
+            .extend(args.iter().map(|a| a.replace("{tools}", &tools)));
+        self
+    }
+
+    /// Arguments that start or continue `session`, `{session}` expanded
+    pub fn session_args(&self, session: &Session) -> Vec<String> {
+        let args = if session.resume {
+            &self.resume_args
+        } else {
+            &self.session_args
+        };
+        args.iter()
+            .map(|a| a.replace("{session}", &session.id))
+            .collect()
+    }
+
+    /// Append the adapter's model arguments, expanding `{model}`
+    pub fn with_model(mut self, args: &[String], model: &str) -> Self {
+        self.argv
+            .extend(args.iter().map(|a| a.replace("{model}", model)));
+        self
+    }
+}
+
+/// Split a command line into argv the way a POSIX shell would for plain
+/// words: single quotes, double quotes with `\"`/`\\` escapes, backslash
+/// escapes outside quotes, and quotes glued to words (`--x='a b'`).
+pub fn split_args(line: &str) -> Result<Vec<String>, SlagError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unbalanced(line, '\'')),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unbalanced(line, '"')),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unbalanced(line, '"')),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn unbalanced(line: &str, quote: char) -> SlagError {
+    SlagError::Config(format!("unbalanced {quote} in smith command: {line}"))
+}
+
+/// Pull the answer out of the agent's stdout
+pub fn extract(output: &Extract, stdout: &str) -> Result<String, SlagError> {
+    match output {
+        Extract::Raw => Ok(stdout.to_string()),
+        Extract::Json(field) => {
+            // Whole stdout first, else the last line that is a JSON object (JSONL streams)
+            let value = serde_json::from_str::<serde_json::Value>(stdout.trim())
+                .ok()
+                .filter(|v| v.is_object())
+                .or_else(|| {
+                    stdout
+                        .lines()
+                        .rev()
+                        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l.trim()).ok())
+                        .find(|v| v.is_object())
+                });
+            // A command override without the JSON flag prints plain text
+            let Some(value) = value else {
+                return Ok(stdout.to_string());
+            };
+            field
+                .split('.')
+                .try_fold(&value, |v, key| v.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    SlagError::SmithFailed(format!("no string field '{field}' in smith output"))
+                })
+        }
+        Extract::StreamJson => stdout
+            .lines()
+            .rev()
+            .filter_map(|l| serde_json::from_str::<serde_json::Value>(l.trim()).ok())
+            .find(|v| v["type"] == "result")
+            .and_then(|v| v["result"].as_str().map(str::to_string))
+            .ok_or_else(|| SlagError::SmithFailed("no result event in smith output".into())),
+        Extract::After(marker) => {
+            let lines: Vec<&str> = stdout.lines().collect();
+            let start = lines
+                .iter()
+                .rposition(|l| l.trim() == marker)
+                .ok_or_else(|| {
+                    SlagError::SmithFailed(format!("marker '{marker}' not in smith output"))
+                })?;
+            Ok(lines[start + 1..].join("\n"))
+        }
+    }
+}
+
+/// What the agent says it used: the `usage` and `total_cost_usd` of its
+/// last JSON object, as claude's json and stream-json outputs report them.
+/// Text outputs carry no usage.
+pub fn usage(output: &Extract, stdout: &str) -> Option<Usage> {
+    if matches!(output, Extract::Raw | Extract::After(_)) {
+        return None;
+    }
+    let value = serde_json::from_str::<serde_json::Value>(stdout.trim())
+        .ok()
+        .into_iter()
+        .chain(
+            stdout
+                .lines()
+                .rev()
+                .filter_map(|l| serde_json::from_str::<serde_json::Value>(l.trim()).ok()),
+        )
+        .find(|v| v.get("usage").is_some_and(|u| u.is_object()))?;
+    let reported = Reported::deserialize(&value["usage"]).ok()?;
+    let cost = value["total_cost_usd"].as_f64().unwrap_or(0.0);
+    Some(Usage::stated(&reported, cost))
+}
+
+/// What an output line says the agent is doing, for the live view: the
+/// latest tool call for stream-json, nothing for a JSON answer, otherwise
+/// the line itself
+pub fn activity(output: &Extract, line: &str) -> Option<String> {
+    let line = line.trim();
+    if matches!(output, Extract::Json(_)) && line.starts_with('{') {
+        return None;
+    }
+    if *output != Extract::StreamJson {
+        return (!line.is_empty()).then(|| line.to_string());
+    }
+    let event: serde_json::Value = serde_json::from_str(line).ok()?;
+    let tool = event["message"]["content"]
+        .as_array()?
+        .iter()
+        .rev()
+        .find(|block| block["type"] == "tool_use")?;
+    let name = tool["name"].as_str().unwrap_or("tool");
+    let input = &tool["input"];
+    let target = ["file_path", "command", "path", "pattern", "url"]
+        .iter()
+        .find_map(|key| input[key].as_str())
+        .unwrap_or("");
+    Some(format!("{name} {target}").trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_basic() {
+        let words = split_args("claude --dangerously-skip-permissions -p").unwrap();
+        assert_eq!(words, ["claude", "--dangerously-skip-permissions", "-p"]);
+    }
+
+    #[test]
+    fn split_args_quoted() {
+        let words = split_args("claude -p --allowedTools 'Bash Edit Read'").unwrap();
+        assert_eq!(words, ["claude", "-p", "--allowedTools", "Bash Edit Read"]);
+        let words = split_args(r#"claude -p --allowedTools "Bash Edit Read""#).unwrap();
+        assert_eq!(words, ["claude", "-p", "--allowedTools", "Bash Edit Read"]);
+    }
+
+    #[test]
+    fn split_args_like_a_shell() {
+        let words = split_args(r#"agent  --name='a b' "say \"hi\"" x\ y '' end"#).unwrap();
+        assert_eq!(
+            words,
+            ["agent", "--name=a b", r#"say "hi""#, "x y", "", "end"]
+        );
+        assert!(split_args("agent 'open").is_err());
+    }
+
+    #[test]
+    fn extracts_answer() {
+        let jsonl =
+            "{\"type\":\"start\"}\n{\"type\":\"result\",\"result\":{\"text\":\"CMD: ok\"}}\n";
+        let out = extract(&Extract::Json("result.text".into()), jsonl).unwrap();
+        assert_eq!(out, "CMD: ok");
+        assert!(extract(&Extract::Json("missing".into()), jsonl).is_err());
+        let plain = "Done.\nCMD: true\n";
+        assert_eq!(
+            extract(&Extract::Json("result".into()), plain).unwrap(),
+            plain
+        );
+
+        let log = "thinking\n--- answer ---\nCMD: true\n";
+        assert_eq!(
+            extract(&Extract::After("--- answer ---".into()), log).unwrap(),
+            "CMD: true"
+        );
+        assert_eq!(extract(&Extract::Raw, log).unwrap(), log);
+    }
+
+    #[test]
+    fn reads_reported_usage() {
+        let json = r#"{"type":"result","result":"CMD: true","total_cost_usd":0.0421,"usage":{"input_tokens":12,"cache_read_input_tokens":9000,"output_tokens":340}}"#;
+        let used = usage(&Extract::Json("result".into()), json).unwrap();
+        assert_eq!(used.input_tokens, 12);
+        assert_eq!(used.cache_read_tokens, 9000);
+        assert_eq!(used.output_tokens, 340);
+        assert_eq!(used.cost_usd, 0.0421);
+
+        let stream = format!("{{\"type\":\"system\"}}\n{json}\n");
+        assert!(usage(&Extract::StreamJson, &stream).is_some());
+        assert!(usage(&Extract::Raw, json).is_none());
+        assert!(usage(&Extract::Json("result".into()), "CMD: true").is_none());
+    }
+
+    #[test]
+    fn reads_stream_json() {
+        let events = [
+            r#"{"type":"system","subtype":"init"}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"ok"},{"type":"tool_use","name":"Edit","input":{"file_path":"src/main.rs"}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+            r#"{"type":"result","subtype":"success","result":"done\nCMD: cargo test"}"#,
+        ];
+        let seen: Vec<String> = events
+            .iter()
+            .filter_map(|e| activity(&Extract::StreamJson, e))
+            .collect();
+        assert_eq!(seen, ["Edit src/main.rs", "Bash cargo test"]);
+
+        let out = extract(&Extract::StreamJson, &events.join("\n")).unwrap();
+        assert_eq!(out, "done\nCMD: cargo test");
+        assert_eq!(
+            activity(&Extract::Raw, "  Applied edit to a.rs \n").unwrap(),
+            "Applied edit to a.rs"
+        );
+    }
+
+    #[test]
+    fn invocation_expands_tools() {
+        let claude = &builtin()["claude"];
+        let inv = Invocation::new(claude, claude.command.clone())
+            .with_args(&claude.tools_args, &["Bash", "Read"]);
+        assert_eq!(inv.argv[5..], ["--allowedTools", "Bash Read"]);
+    }
+}
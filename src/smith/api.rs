@@ -0,0 +1,207 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::{Deserialize, Serialize};
+
+use super::usage::{self, Prices, Reported, Usage};
+use super::{http, Smith};
+use crate::config::ApiSettings;
+use crate::error::SlagError;
+
+/// Messages API version header value
+const API_VERSION: &str = "2023-06-01";
+
+/// Smith that calls the Messages API directly over HTTP.
+/// Text in, text out: edits come back as file blocks.
+pub struct ApiSmith {
+    settings: ApiSettings,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    messages: [Message<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<Reported>,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+impl ApiSmith {
+    pub fn new(settings: ApiSettings, api_key: Option<String>) -> Self {
+        Self {
+            settings,
+            api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Read the key from the env var named in `api_key_env`
+    pub fn from_settings(settings: &ApiSettings) -> Self {
+        let api_key = std::env::var(&settings.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty());
+        Self::new(settings.clone(), api_key)
+    }
+
+    async fn invoke_impl(&self, prompt: &str) -> Result<String, SlagError> {
+        let api_key = self.api_key.as_deref().ok_or_else(|| {
+            SlagError::SmithFailed(format!("{} is not set", self.settings.api_key_env))
+        })?;
+        let url = format!(
+            "{}/v1/messages",
+            self.settings.base_url.trim_end_matches('/')
+        );
+        let body = Request {
+            model: &self.settings.model,
+            max_tokens: self.settings.max_tokens,
+            messages: [Message {
+                role: "user",
+                content: prompt,
+            }],
+        };
+
+        let response = http::send(
+            self.client
+                .post(&url)
+                .header("x-api-key", api_key)
+                .header("anthropic-version", API_VERSION)
+                .json(&body),
+        )
+        .await?;
+        let parsed: Response = http::json(response).await?;
+        if let Some(reported) = &parsed.usage {
+            usage::report(Usage::priced(
+                reported,
+                Prices {
+                    input: self.settings.input_price,
+                    output: self.settings.output_price,
+                },
+            ));
+        }
+        Ok(response_text(parsed))
+    }
+}
+
+impl Smith for ApiSmith {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move { self.invoke_impl(&prompt).await })
+    }
+
+    fn edits_files(&self) -> bool {
+        false
+    }
+}
+
+/// Join the text blocks of a response
+fn response_text(response: Response) -> String {
+    response
+        .content
+        .into_iter()
+        .filter(|b| b.kind == "text")
+        .map(|b| b.text)
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smith::http::stand_in;
+
+    fn smith(base_url: String) -> ApiSmith {
+        let settings = ApiSettings {
+            base_url,
+            model: "test-model".into(),
+            ..ApiSettings::default()
+        };
+        ApiSmith::new(settings, Some("sk-test".into()))
+    }
+
+    const OK: &str = r#"{"content":[{"type":"text","text":"CMD: "},{"type":"tool_use","id":"x"},{"type":"text","text":"cargo test"}]}"#;
+
+    #[tokio::test]
+    async fn sends_prompt_and_joins_text_blocks() {
+        let (url, server) = stand_in::serve(vec![(200, OK)]).await;
+        let out = smith(url).invoke("build it").await.unwrap();
+        assert_eq!(out, "CMD: cargo test");
+
+        let request = &server.await.unwrap()[0];
+        assert!(request.starts_with("POST /v1/messages "));
+        assert!(request.contains("x-api-key: sk-test"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+        assert!(request.contains(r#""model":"test-model""#));
+        assert!(request.contains(r#""content":"build it""#));
+    }
+
+    #[tokio::test]
+    async fn overload_is_transient_and_left_to_the_retrier() {
+        let overloaded = r#"{"error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let (url, server) = stand_in::serve(vec![(529, overloaded)]).await;
+        let err = smith(url).invoke("x").await.unwrap_err();
+        assert!(
+            matches!(&err, SlagError::SmithTransient(m) if m.contains("Overloaded")),
+            "{err}"
+        );
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retrier_retries_the_overloaded_request() {
+        use crate::config::TransientSettings;
+        use crate::smith::retry::{Breaker, Retrying};
+        use std::time::Duration;
+
+        let (url, server) = stand_in::serve(vec![(529, "{}"), (500, "{}"), (200, OK)]).await;
+        let policy = TransientSettings {
+            retries: 2,
+            base_delay_ms: 1,
+            max_delay_ms: 1,
+            ..TransientSettings::default()
+        };
+        let breaker = Box::leak(Box::new(Breaker::new(100, Duration::from_secs(1))));
+        let smith = Retrying::wrap(Box::new(smith(url)), policy, breaker);
+        assert_eq!(smith.invoke("x").await.unwrap(), "CMD: cargo test");
+        assert_eq!(server.await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn client_errors_fail_without_retry() {
+        let bad = r#"{"error":{"type":"invalid_request_error","message":"max_tokens too large"}}"#;
+        let (url, server) = stand_in::serve(vec![(400, bad)]).await;
+        let err = smith(url).invoke("x").await.unwrap_err().to_string();
+        assert!(err.contains("max_tokens too large"), "{err}");
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn missing_key_is_reported() {
+        let smith = ApiSmith::new(ApiSettings::default(), None);
+        let err = smith.invoke("x").await.unwrap_err().to_string();
+        assert!(err.contains("ANTHROPIC_API_KEY"), "{err}");
+    }
+}
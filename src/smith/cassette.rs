@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Smith, Tap};
+use crate::config::Phase;
+use crate::error::SlagError;
+
+/// Fuzzy matches below this similarity are not served
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// One recorded smith call: a line of a cassette (JSON lines)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Take {
+    pub phase: Phase,
+    /// FNV-1a of the prompt, hex
+    pub hash: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// Set instead of `response` when the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether the recorded smith edited files itself; decides the flux
+    #[serde(default = "yes")]
+    pub edits_files: bool,
+    /// Whether the recorded smith kept sessions; decides later heats' flux
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sessions: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl Take {
+    pub fn new(phase: Phase, prompt: &str, result: &Result<String, SlagError>) -> Self {
+        Self {
+            phase,
+            hash: hash(prompt),
+            prompt: prompt.to_string(),
+            response: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            edits_files: true,
+            sessions: false,
+        }
+    }
+
+    fn replay(&self) -> Result<String, SlagError> {
+        match (&self.response, &self.error) {
+            (Some(response), _) => Ok(response.clone()),
+            (None, error) => Err(SlagError::SmithFailed(format!(
+                "replayed: {}",
+                error.as_deref().unwrap_or("no response recorded")
+            ))),
+        }
+    }
+}
+
+/// Stable 64-bit FNV-1a, so cassettes match across builds and platforms
+pub fn hash(prompt: &str) -> String {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in prompt.as_bytes() {
+        h ^= u64::from(*byte);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{h:016x}")
+}
+
+/// Appends every take of a run to one cassette file
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Start a new cassette at `path`, replacing any old one
+    pub fn create(path: &Path) -> Result<Self, SlagError> {
+        let file = File::create(path)
+            .map_err(|e| SlagError::Config(format!("cannot record to {}: {e}", path.display())))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn write(&self, take: &Take) {
+        let Ok(mut line) = serde_json::to_string(take) else {
+            return;
+        };
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = file.write_all(line.as_bytes());
+    }
+}
+
+/// A smith whose calls are written to a cassette
+pub struct RecordingSmith {
+    inner: Box<dyn Smith>,
+    recorder: Arc<Recorder>,
+    phase: Phase,
+}
+
+impl RecordingSmith {
+    pub fn wrap(smith: Box<dyn Smith>, recorder: Arc<Recorder>, phase: Phase) -> Box<dyn Smith> {
+        Box::new(Self {
+            inner: smith,
+            recorder,
+            phase,
+        })
+    }
+
+    async fn record(
+        &self,
+        prompt: &str,
+        call: impl Future<Output = Result<String, SlagError>>,
+    ) -> Result<String, SlagError> {
+        let result = call.await;
+        let mut take = Take::new(self.phase, prompt, &result);
+        take.edits_files = self.inner.edits_files();
+        take.sessions = self.inner.sessions();
+        self.recorder.write(&take);
+        result
+    }
+}
+
+impl Smith for RecordingSmith {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move { self.record(&prompt, self.inner.invoke(&prompt)).await })
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        Box::pin(self.record(prompt, self.inner.invoke_tapped(prompt, tap)))
+    }
+
+    fn edits_files(&self) -> bool {
+        self.inner.edits_files()
+    }
+
+    fn sessions(&self) -> bool {
+        self.inner.sessions()
+    }
+}
+
+/// The takes of a recorded run, served back in place of a smith.
+/// Each take is served once while unserved ones remain.
+#[derive(Debug)]
+pub struct Cassette {
+    takes: Vec<Take>,
+    served: Mutex<Vec<bool>>,
+}
+
+impl Cassette {
+    pub fn new(takes: Vec<Take>) -> Self {
+        let served = Mutex::new(vec![false; takes.len()]);
+        Self { takes, served }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SlagError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SlagError::Config(format!("cannot replay {}: {e}", path.display())))?;
+        let takes = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    SlagError::Config(format!("{} line {}: {e}", path.display(), n + 1))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(takes))
+    }
+
+    pub fn takes(&self) -> &[Take] {
+        &self.takes
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SlagError> {
+        let recorder = Recorder::create(path)?;
+        for take in &self.takes {
+            recorder.write(take);
+        }
+        Ok(())
+    }
+
+    /// The take for `prompt` in `phase`: the same prompt hash first, else the
+    /// most similar prompt of the phase. Unserved takes win over served ones.
+    pub fn find(&self, phase: Phase, prompt: &str) -> Option<&Take> {
+        let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+        let key = hash(prompt);
+        let in_phase = || (0..self.takes.len()).filter(|&i| self.takes[i].phase == phase);
+
+        let exact: Vec<usize> = in_phase().filter(|&i| self.takes[i].hash == key).collect();
+        let pick = exact
+            .iter()
+            .copied()
+            .find(|&i| !served[i])
+            .or_else(|| exact.last().copied())
+            .or_else(|| {
+                let closest = |unserved: bool| {
+                    in_phase()
+                        .filter(|&i| !unserved || !served[i])
+                        .map(|i| (i, similarity(prompt, &self.takes[i].prompt)))
+                        .filter(|(_, score)| *score >= MIN_SIMILARITY)
+                        .max_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(i, _)| i)
+                };
+                closest(true).or_else(|| closest(false))
+            })?;
+        served[pick] = true;
+        Some(&self.takes[pick])
+    }
+
+    /// Whether the recorded smith for `phase` edited files itself
+    pub fn edits_files(&self, phase: Phase) -> bool {
+        self.takes
+            .iter()
+            .find(|t| t.phase == phase)
+            .or(self.takes.first())
+            .is_none_or(|t| t.edits_files)
+    }
+
+    /// Whether the recorded smith for `phase` kept sessions
+    pub fn sessions(&self, phase: Phase) -> bool {
+        self.takes.iter().any(|t| t.phase == phase && t.sessions)
+    }
+
+    /// Rebuild a cassette from a run's log directory, pairing each prompt log
+    /// with the response log that follows it: SURVEY_PROMPT/SURVEY_RAW,
+    /// FOUNDER_PROMPT/FOUNDER_RAW, FLUX_<id>_<heat>/STRIKE_<id>_<heat>,
+    /// RESMELT_<id>/RESMELT_RESULT_<id> and RECONSIDER_<id>/RECONSIDER_RESULT_<id>.
+    pub fn from_logs(dir: &Path) -> Result<Self, SlagError> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.ends_with(".log"))
+            .collect();
+        // Timestamp prefixes sort chronologically
+        names.sort();
+
+        let mut pending: HashMap<String, (Phase, String)> = HashMap::new();
+        let mut takes = Vec::new();
+        for name in &names {
+            let Some(label) = log_label(name) else {
+                continue;
+            };
+            let content = || std::fs::read_to_string(dir.join(name));
+            if let Some((phase, answer)) = prompt_label(label) {
+                pending.insert(answer, (phase, content()?));
+            } else if let Some((phase, prompt)) = pending.remove(label) {
+                let mut take = Take::new(phase, &prompt, &Ok(content()?));
+                // Text-only smiths were asked for file blocks
+                take.edits_files = !prompt.contains(crate::flux::FILE_BLOCK_PROTOCOL.trim());
+                takes.push(take);
+            }
+        }
+        Ok(Self::new(takes))
+    }
+}
+
+/// `20260127_094623_FLUX_i1_1.log` -> `FLUX_i1_1`
+fn log_label(name: &str) -> Option<&str> {
+    let stem = name.strip_suffix(".log")?;
+    let mut parts = stem.splitn(3, '_');
+    let (date, time) = (parts.next()?, parts.next()?);
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    (all_digits(date) && all_digits(time)).then_some(())?;
+    parts.next()
+}
+
+/// For a prompt log label, its phase and the label of the answer log
+fn prompt_label(label: &str) -> Option<(Phase, String)> {
+    match label {
+        "SURVEY_PROMPT" => Some((Phase::Survey, "SURVEY_RAW".into())),
+        "FOUNDER_PROMPT" => Some((Phase::Found, "FOUNDER_RAW".into())),
+        _ if label.starts_with("RESMELT_RESULT_") || label.starts_with("RECONSIDER_RESULT_") => {
+            None
+        }
+        _ => {
+            if let Some(rest) = label.strip_prefix("FLUX_") {
+                Some((Phase::Forge, format!("STRIKE_{rest}")))
+            } else if let Some(id) = label.strip_prefix("RESMELT_") {
+                Some((Phase::Resmelt, format!("RESMELT_RESULT_{id}")))
+            } else {
+                let id = label.strip_prefix("RECONSIDER_")?;
+                Some((Phase::Reconsider, format!("RECONSIDER_RESULT_{id}")))
+            }
+        }
+    }
+}
+
+/// Dice coefficient over the prompts' lines (as multisets), 0.0 to 1.0.
+/// Prompts of one phase share a template; the lines that differ say which
+/// ingot and heat they are for.
+fn similarity(a: &str, b: &str) -> f64 {
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    let (mut total_a, mut total_b) = (0, 0);
+    for line in a.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        *counts.entry(line).or_default() += 1;
+        total_a += 1;
+    }
+    let mut shared = 0;
+    for line in b.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        total_b += 1;
+        if let Some(n) = counts.get_mut(line) {
+            if *n > 0 {
+                *n -= 1;
+                shared += 1;
+            }
+        }
+    }
+    if total_a + total_b == 0 {
+        return 1.0;
+    }
+    2.0 * shared as f64 / (total_a + total_b) as f64
+}
+
+/// Smith that answers from a cassette instead of calling a backend
+pub struct ReplaySmith {
+    cassette: Arc<Cassette>,
+    phase: Phase,
+}
+
+impl ReplaySmith {
+    pub fn new(cassette: Arc<Cassette>, phase: Phase) -> Self {
+        Self { cassette, phase }
+    }
+}
+
+impl Smith for ReplaySmith {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let result = match self.cassette.find(self.phase, prompt) {
+            Some(take) => take.replay(),
+            None => Err(SlagError::SmithFailed(format!(
+                "replay: no recorded {:?} call matches prompt {}",
+                self.phase,
+                hash(prompt)
+            ))),
+        };
+        Box::pin(async move { result })
+    }
+
+    fn edits_files(&self) -> bool {
+        self.cassette.edits_files(self.phase)
+    }
+
+    fn sessions(&self) -> bool {
+        self.cassette.sessions(self.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(phase: Phase, prompt: &str, response: &str) -> Take {
+        Take::new(phase, prompt, &Ok(response.to_string()))
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        assert_eq!(hash(""), "cbf29ce484222325");
+        assert_eq!(hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[tokio::test]
+    async fn records_then_replays() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        let smith = RecordingSmith::wrap(
+            Box::new(super::super::mock::MockSmith::fixed("CMD: true")),
+            recorder.clone(),
+            Phase::Forge,
+        );
+        smith.invoke("forge i1").await.unwrap();
+        let failing = RecordingSmith::wrap(
+            Box::new(super::super::mock::MockSmith::failing()),
+            recorder,
+            Phase::Survey,
+        );
+        assert!(failing.invoke("survey").await.is_err());
+
+        let cassette = Arc::new(Cassette::load(&path).unwrap());
+        assert_eq!(cassette.takes().len(), 2);
+        let replay = ReplaySmith::new(cassette.clone(), Phase::Forge);
+        assert_eq!(replay.invoke("forge i1").await.unwrap(), "CMD: true");
+        let err = ReplaySmith::new(cassette, Phase::Survey)
+            .invoke("survey")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no responses"), "{err}");
+    }
+
+    #[test]
+    fn serves_in_order_then_falls_back_to_closest() {
+        let flux = |id: &str, diff: &str| {
+            format!("FLUX\nrules\nmore rules\nINGOT: {id}\nwork for {id}\nDIFF: {diff}\n")
+        };
+        let cassette = Cassette::new(vec![
+            take(Phase::Forge, &flux("i1", "a"), "first"),
+            take(Phase::Forge, &flux("i1", "a"), "second"),
+            take(Phase::Forge, &flux("i2", "a"), "other"),
+        ]);
+
+        // Same prompt twice: each recorded answer in turn, then the last again
+        assert_eq!(
+            cassette
+                .find(Phase::Forge, &flux("i1", "a"))
+                .unwrap()
+                .response
+                .as_deref(),
+            Some("first")
+        );
+        assert_eq!(
+            cassette
+                .find(Phase::Forge, &flux("i1", "a"))
+                .unwrap()
+                .response
+                .as_deref(),
+            Some("second")
+        );
+        assert_eq!(
+            cassette
+                .find(Phase::Forge, &flux("i1", "a"))
+                .unwrap()
+                .response
+                .as_deref(),
+            Some("second")
+        );
+
+        // A changed diff still finds its ingot
+        let close = cassette.find(Phase::Forge, &flux("i2", "b")).unwrap();
+        assert_eq!(close.response.as_deref(), Some("other"));
+
+        assert!(cassette.find(Phase::Survey, &flux("i1", "a")).is_none());
+        assert!(cassette.find(Phase::Forge, "nothing alike").is_none());
+    }
+
+    #[test]
+    fn rebuilds_from_example_logs() {
+        let logs = Path::new(env!("CARGO_MANIFEST_DIR")).join("example/logs");
+        let cassette = Cassette::from_logs(&logs).unwrap();
+        let strike = cassette
+            .takes()
+            .iter()
+            .find(|t| t.phase == Phase::Forge)
+            .unwrap();
+        let flux = std::fs::read_dir(&logs)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| {
+                std::fs::read_to_string(p).is_ok_and(|c| c == strike.prompt)
+                    && p.to_string_lossy().contains("FLUX_")
+            });
+        assert!(flux.is_some());
+        assert!(cassette.takes().iter().any(|t| t.phase == Phase::Found));
+        assert!(cassette.find(Phase::Forge, &strike.prompt).is_some());
+    }
+}
@@ -0,0 +1,331 @@
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use super::adapter::{self, Extract, Invocation, PromptVia};
+use super::{retry, usage, Smith, Tap};
+use crate::error::SlagError;
+use crate::shutdown::ChildGroup;
+
+/// Smith that spawns a CLI coding agent (claude, codex, aider, ...)
+/// as described by its adapter.
+pub struct CliSmith {
+    invocation: Invocation,
+}
+
+impl CliSmith {
+    pub fn new(invocation: Invocation) -> Self {
+        Self { invocation }
+    }
+
+    async fn invoke_impl(&self, prompt: &str, tap: &Tap) -> Result<String, SlagError> {
+        let inv = &self.invocation;
+        let Some((program, args)) = inv.argv.split_first() else {
+            return Err(SlagError::SmithFailed("empty smith command".into()));
+        };
+
+        let mut cmd = match &tap.sandbox {
+            Some(sandbox) => sandbox.command(program),
+            None => Command::new(program),
+        };
+        cmd.args(args);
+        if let Some(session) = &tap.session {
+            cmd.args(inv.session_args(session));
+        }
+        // Removed when the invocation ends, however it ends
+        let mut _prompt_file = None;
+        match inv.prompt {
+            PromptVia::Stdin => {
+                cmd.stdin(std::process::Stdio::piped());
+            }
+            PromptVia::Arg => {
+                cmd.args(&inv.prompt_flag).arg(prompt);
+                cmd.stdin(std::process::Stdio::null());
+            }
+            PromptVia::File => {
+                let file = PromptFile::write(prompt)
+                    .map_err(|e| SlagError::SmithFailed(format!("prompt file: {e}")))?;
+                cmd.args(&inv.prompt_flag).arg(&file.0);
+                cmd.stdin(std::process::Stdio::null());
+                _prompt_file = Some(file);
+            }
+        }
+
+        // Own process group, so cancelling the invocation also stops the tools it ran
+        let mut child = cmd
+            .process_group(0)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| SlagError::SmithFailed(format!("failed to spawn {program}: {e}")))?;
+        let group = ChildGroup::track(&child);
+
+        // Feed stdin while draining both pipes, so neither side can stall the other
+        let stdin = child.stdin.take();
+        let feed = async {
+            if let Some(mut stdin) = stdin {
+                stdin.write_all(prompt.as_bytes()).await?;
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        let sink = LineSink::new(tap, &inv.output);
+        let (fed, stdout, stderr) = tokio::join!(
+            feed,
+            read_lines(child.stdout.take(), |l| sink.stdout(l)),
+            read_lines(child.stderr.take(), |l| sink.stderr(l)),
+        );
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| SlagError::SmithFailed(format!("wait failed: {e}")))?;
+        group.finish();
+
+        if !status.success() {
+            // Agents print API errors on either stream
+            let output = if stderr.trim().is_empty() {
+                &stdout
+            } else {
+                &stderr
+            };
+            return Err(retry::classify(status.code(), output));
+        }
+        // A smith that exits cleanly without reading all of stdin is fine
+        match fed {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                return Err(SlagError::SmithFailed(format!("stdin write failed: {e}")));
+            }
+            _ => {}
+        }
+
+        if let Some(used) = adapter::usage(&inv.output, &stdout) {
+            usage::report(used);
+        }
+        adapter::extract(&inv.output, &stdout)
+    }
+}
+
+impl Smith for CliSmith {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move { self.invoke_impl(&prompt, &Tap::default()).await })
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        Box::pin(self.invoke_impl(prompt, tap))
+    }
+
+    fn sessions(&self) -> bool {
+        !self.invocation.session_args.is_empty() && !self.invocation.resume_args.is_empty()
+    }
+}
+
+/// Read a pipe line by line as output arrives; returns everything read
+async fn read_lines(pipe: Option<impl AsyncRead + Unpin>, on_line: impl Fn(&str)) -> String {
+    let mut all = String::new();
+    let Some(pipe) = pipe else {
+        return all;
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                on_line(&line);
+                all.push_str(&line);
+            }
+        }
+    }
+    all
+}
+
+/// Where streamed lines go: the tap's log file and activity channel
+struct LineSink<'a> {
+    log: Option<Mutex<std::fs::File>>,
+    activity: Option<&'a tokio::sync::watch::Sender<String>>,
+    output: &'a Extract,
+}
+
+impl<'a> LineSink<'a> {
+    fn new(tap: &'a Tap, output: &'a Extract) -> Self {
+        let log = tap.log.as_ref().and_then(|path| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .ok()
+        });
+        Self {
+            log: log.map(Mutex::new),
+            activity: tap.activity.as_ref(),
+            output,
+        }
+    }
+
+    fn stdout(&self, line: &str) {
+        self.write(line);
+        if let Some(tx) = self.activity {
+            if let Some(activity) = adapter::activity(self.output, line) {
+                tx.send_replace(activity);
+            }
+        }
+    }
+
+    fn stderr(&self, line: &str) {
+        self.write(&format!("[stderr] {line}"));
+    }
+
+    fn write(&self, line: &str) {
+        if let Some(log) = &self.log {
+            let mut file = log.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = file.write_all(line.as_bytes());
+            if !line.ends_with('\n') {
+                let _ = file.write_all(b"\n");
+            }
+        }
+    }
+}
+
+/// Prompt handed to the agent as a file; deleted on drop
+struct PromptFile(PathBuf);
+
+impl PromptFile {
+    fn write(prompt: &str) -> std::io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "slag-prompt-{}-{}.md",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, prompt)?;
+        Ok(PromptFile(path))
+    }
+}
+
+impl Drop for PromptFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smith::adapter::Extract;
+    use crate::smith::session::Session;
+
+    fn bash(script: &str, prompt: PromptVia, output: Extract) -> CliSmith {
+        CliSmith::new(Invocation {
+            argv: vec!["bash".into(), "-c".into(), script.into(), "agent".into()],
+            prompt,
+            prompt_flag: Some("--msg".into()),
+            session_args: vec!["--session-id".into(), "{session}".into()],
+            resume_args: vec!["--resume".into(), "{session}".into()],
+            output,
+        })
+    }
+
+    #[tokio::test]
+    async fn delivers_prompt_each_way() {
+        let stdin = bash("cat", PromptVia::Stdin, Extract::Raw);
+        assert_eq!(stdin.invoke("hello").await.unwrap(), "hello");
+
+        // $1 is the flag, $2 the prompt
+        let arg = bash(r#"echo "$1 $2""#, PromptVia::Arg, Extract::Raw);
+        assert_eq!(arg.invoke("hello").await.unwrap(), "--msg hello\n");
+
+        let file = bash(
+            r#"cat "$2"; echo; echo "$2""#,
+            PromptVia::File,
+            Extract::Raw,
+        );
+        let out = file.invoke("hello").await.unwrap();
+        let (content, path) = out.split_once('\n').unwrap();
+        assert_eq!(content, "hello");
+        assert!(!std::path::Path::new(path.trim()).exists());
+    }
+
+    #[tokio::test]
+    async fn extracts_json_answer() {
+        let smith = bash(
+            r#"echo '{"result":"CMD: true"}'"#,
+            PromptVia::Stdin,
+            Extract::Json("result".into()),
+        );
+        assert_eq!(smith.invoke("x").await.unwrap(), "CMD: true");
+    }
+
+    #[tokio::test]
+    async fn overridden_plain_command_reads_raw_output() {
+        // `SLAG_SMITH="claude -p"` keeps the json adapter's output setting
+        let smith = bash(
+            "echo Done.; echo 'CMD: true'",
+            PromptVia::Stdin,
+            Extract::Json("result".into()),
+        );
+        assert_eq!(smith.invoke("x").await.unwrap(), "Done.\nCMD: true\n");
+    }
+
+    #[tokio::test]
+    async fn tees_lines_and_reports_activity() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = tokio::sync::watch::channel(String::new());
+        let tap = Tap {
+            log: Some(dir.path().join("live.log")),
+            activity: Some(tx),
+            ..Tap::default()
+        };
+        let smith = bash(
+            "echo 'Editing a.rs'; echo oops >&2; echo 'CMD: true'",
+            PromptVia::Stdin,
+            Extract::Raw,
+        );
+        let out = smith.invoke_tapped("x", &tap).await.unwrap();
+        assert_eq!(out, "Editing a.rs\nCMD: true\n");
+        assert_eq!(*rx.borrow(), "CMD: true");
+
+        let log = std::fs::read_to_string(dir.path().join("live.log")).unwrap();
+        assert!(log.contains("Editing a.rs\n"));
+        assert!(log.contains("[stderr] oops\n"));
+    }
+
+    #[tokio::test]
+    async fn starts_then_resumes_a_session() {
+        let smith = bash(r#"echo "$1 $2""#, PromptVia::Stdin, Extract::Raw);
+        assert!(smith.sessions());
+        let mut tap = Tap {
+            session: Some(Session::start()),
+            ..Tap::default()
+        };
+        let id = tap.session.as_ref().unwrap().id.clone();
+        let first = smith.invoke_tapped("x", &tap).await.unwrap();
+        assert_eq!(first, format!("--session-id {id}\n"));
+
+        tap.session = Some(Session::resume(&id));
+        let later = smith.invoke_tapped("x", &tap).await.unwrap();
+        assert_eq!(later, format!("--resume {id}\n"));
+    }
+
+    #[tokio::test]
+    async fn failure_reports_stderr() {
+        let smith = bash("echo nope >&2; exit 3", PromptVia::Stdin, Extract::Raw);
+        let err = smith.invoke("x").await.unwrap_err().to_string();
+        assert!(err.contains("exit 3: nope"), "{err}");
+    }
+}
@@ -0,0 +1,111 @@
+use serde::Deserialize;
+
+use crate::error::SlagError;
+
+/// Error body shared by the Messages and chat-completions APIs
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+/// Send `request` once. Connection failures, rate limits and 5xx come back
+/// as `SmithTransient`, for `retry::Retrying` to back off and try again;
+/// other error statuses fail outright.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, SlagError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| SlagError::SmithTransient(format!("api request failed: {e}")))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let detail = response
+        .json::<ErrorBody>()
+        .await
+        .map(|b| b.error.message)
+        .unwrap_or_default();
+    let message = format!("api {status}: {detail}");
+    if retryable(status) {
+        Err(SlagError::SmithTransient(message))
+    } else {
+        Err(SlagError::SmithFailed(message))
+    }
+}
+
+/// Rate limits, overload (529) and server errors
+fn retryable(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Decode a successful response body
+pub async fn json<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, SlagError> {
+    response
+        .json()
+        .await
+        .map_err(|e| SlagError::SmithFailed(format!("bad response: {e}")))
+}
+
+/// Local stand-in server for HTTP smith tests
+#[cfg(test)]
+pub mod stand_in {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::task::JoinHandle;
+
+    /// Answer each connection with the next canned `(status, body)`.
+    /// Returns the base URL and a handle yielding the raw requests received.
+    pub async fn serve(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut socket).await);
+                let reply = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> String {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            let Some(end) = text.find("\r\n\r\n") else {
+                if n == 0 {
+                    return text.into_owned();
+                }
+                continue;
+            };
+            let length = text[..end]
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().to_string())
+                })
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            if n == 0 || buf.len() >= end + 4 + length {
+                return text.into_owned();
+            }
+        }
+    }
+}
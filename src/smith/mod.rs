@@ -1,12 +1,25 @@
-pub mod claude;
+pub mod adapter;
+pub mod api;
+pub mod cassette;
+pub mod cli;
+pub mod http;
 pub mod mock;
+pub mod openai;
+pub mod retry;
+pub mod session;
+pub mod usage;
 
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::time::Duration;
 
+use crate::config::{ApiSettings, Backend, OpenAiSettings, Phase, Routed, SmithConfig};
 use crate::error::SlagError;
+use crate::sexp::Ingot;
+use adapter::Invocation;
 
-/// Async trait for invoking an AI smith (Claude or mock).
+/// Async trait for invoking an AI smith (CLI agent, HTTP API or mock).
 /// Uses boxed future for dyn compatibility.
 pub trait Smith: Send + Sync {
     /// Send a prompt and receive the response text.
@@ -14,6 +27,233 @@ pub trait Smith: Send + Sync {
         &self,
         prompt: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>>;
+
+    /// Like `invoke`, but report progress through `tap` while running.
+    /// Smiths that cannot stream just invoke.
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        let _ = tap;
+        self.invoke(prompt)
+    }
+
+    /// Whether the smith edits the working tree itself. Text-only smiths
+    /// return false and send file blocks for slag to apply instead.
+    fn edits_files(&self) -> bool {
+        true
+    }
+
+    /// Whether the smith honours `Tap::session`, keeping one agent
+    /// conversation across invocations
+    fn sessions(&self) -> bool {
+        false
+    }
+}
+
+/// Hands the pipeline its smiths. `SmithConfig` builds them from settings;
+/// tests pass scripted ones.
+pub trait SmithFactory: Send + Sync {
+    /// Smith for a whole-run phase: survey, found, resmelt, reconsider, review or analysis
+    fn phase(&self, phase: Phase) -> Box<dyn Smith>;
+
+    /// Smith that forges `ingot`, picked by its skill and grade
+    fn forge(&self, ingot: &Ingot) -> Box<dyn Smith>;
+}
+
+impl SmithFactory for SmithConfig {
+    fn phase(&self, phase: Phase) -> Box<dyn Smith> {
+        // Survey and found plan; everything after works on code
+        let invocation = match phase {
+            Phase::Survey | Phase::Found => &self.plan,
+            _ => &self.base,
+        };
+        build(self, phase, invocation)
+    }
+
+    fn forge(&self, ingot: &Ingot) -> Box<dyn Smith> {
+        let invocation = self.select(ingot.skill.as_str(), ingot.grade);
+        for_forge(self, ingot, invocation)
+    }
+}
+
+/// Live view of a streaming invocation: output lines are appended to `log`
+/// as they arrive, and the latest tool call or file edit is published on
+/// `activity`. `session` names the conversation the invocation belongs to,
+/// and a CLI smith runs inside `sandbox` when one is set.
+#[derive(Debug, Default)]
+pub struct Tap {
+    pub log: Option<PathBuf>,
+    pub activity: Option<tokio::sync::watch::Sender<String>>,
+    pub session: Option<session::Session>,
+    pub sandbox: Option<crate::sandbox::Sandbox>,
+}
+
+/// Build the smith for a phase: the CLI agent `invocation`, or the HTTP
+/// backend `[smith]` routes the phase to, limited by the phase's timeout.
+pub fn build(config: &SmithConfig, phase: Phase, invocation: &Invocation) -> Box<dyn Smith> {
+    let route = config.route(phase, None);
+    let smith = from_backend(config, phase, &route, invocation);
+    let smith = harden(config, Timed::wrap(smith, config.timeouts.for_phase(phase)));
+    Box::new(Metered {
+        inner: record(config, phase, smith),
+        phase,
+    })
+}
+
+/// Build the smith that forges an ingot; its `:timeout` overrides `[timeouts] strike`.
+/// Unmetered: the forge charges each heat to its ingot itself.
+pub fn for_forge(config: &SmithConfig, ingot: &Ingot, invocation: &Invocation) -> Box<dyn Smith> {
+    let route = config.route(Phase::Forge, Some(ingot.grade));
+    let smith = from_backend(config, Phase::Forge, &route, invocation);
+    let secs = ingot.timeout.map_or(config.timeouts.strike, u64::from);
+    record(
+        config,
+        Phase::Forge,
+        harden(config, Timed::wrap(smith, secs)),
+    )
+}
+
+/// Retry transient failures (each attempt with its own timeout) behind the run's breaker
+fn harden(config: &SmithConfig, smith: Box<dyn Smith>) -> Box<dyn Smith> {
+    retry::Retrying::wrap(smith, config.transient.clone(), retry::Breaker::global())
+}
+
+/// Write the smith's calls, as the pipeline sees them, to the run's cassette
+fn record(config: &SmithConfig, phase: Phase, smith: Box<dyn Smith>) -> Box<dyn Smith> {
+    match &config.recorder {
+        Some(recorder) => cassette::RecordingSmith::wrap(smith, recorder.clone(), phase),
+        None => smith,
+    }
+}
+
+/// The routed backend, on the routed model if any. A replay cassette
+/// stands in for every backend.
+fn from_backend(
+    config: &SmithConfig,
+    phase: Phase,
+    route: &Routed,
+    invocation: &Invocation,
+) -> Box<dyn Smith> {
+    if let Some(cassette) = &config.cassette {
+        return Box::new(cassette::ReplaySmith::new(cassette.clone(), phase));
+    }
+    let model = route.model.as_deref();
+    match route.backend {
+        Backend::Cli => {
+            let invocation = match model {
+                Some(model) => invocation.clone().with_model(&config.model_args, model),
+                None => invocation.clone(),
+            };
+            Box::new(cli::CliSmith::new(invocation))
+        }
+        Backend::Api => Box::new(api::ApiSmith::from_settings(&ApiSettings {
+            model: model.map_or_else(|| config.api.model.clone(), String::from),
+            ..config.api.clone()
+        })),
+        Backend::Openai => Box::new(openai::OpenAiSmith::from_settings(&OpenAiSettings {
+            model: model.map_or_else(|| config.openai.model.clone(), String::from),
+            ..config.openai.clone()
+        })),
+    }
+}
+
+/// A smith with a deadline on every invocation. On expiry the invocation is
+/// dropped, which kills the smith's whole process group.
+pub struct Timed {
+    inner: Box<dyn Smith>,
+    limit: Duration,
+}
+
+impl Timed {
+    /// Limit `smith` to `secs` per invocation; 0 leaves it unlimited
+    pub fn wrap(smith: Box<dyn Smith>, secs: u64) -> Box<dyn Smith> {
+        if secs == 0 {
+            return smith;
+        }
+        Box::new(Timed {
+            inner: smith,
+            limit: Duration::from_secs(secs),
+        })
+    }
+
+    async fn within(
+        &self,
+        invocation: impl Future<Output = Result<String, SlagError>>,
+    ) -> Result<String, SlagError> {
+        tokio::time::timeout(self.limit, invocation)
+            .await
+            .unwrap_or(Err(SlagError::SmithTimeout(self.limit.as_secs())))
+    }
+}
+
+impl Smith for Timed {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move { self.within(self.inner.invoke(&prompt)).await })
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        Box::pin(self.within(self.inner.invoke_tapped(prompt, tap)))
+    }
+
+    fn edits_files(&self) -> bool {
+        self.inner.edits_files()
+    }
+
+    fn sessions(&self) -> bool {
+        self.inner.sessions()
+    }
+}
+
+/// A phase smith whose usage is charged to its phase
+struct Metered {
+    inner: Box<dyn Smith>,
+    phase: Phase,
+}
+
+impl Smith for Metered {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move {
+            let call = self.inner.invoke(&prompt);
+            usage::metered(usage::Scope::phase(self.phase), call)
+                .await
+                .0
+        })
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        let call = self.inner.invoke_tapped(prompt, tap);
+        Box::pin(async move {
+            usage::metered(usage::Scope::phase(self.phase), call)
+                .await
+                .0
+        })
+    }
+
+    fn edits_files(&self) -> bool {
+        self.inner.edits_files()
+    }
+
+    fn sessions(&self) -> bool {
+        self.inner.sessions()
+    }
 }
 
 /// Check if response text contains unresolved questions
@@ -66,6 +306,45 @@ pub async fn self_iterate(
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn timeout_kills_the_smith_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let smith = cli::CliSmith::new(Invocation {
+            argv: vec!["bash".into(), "-c".into(), script],
+            prompt: adapter::PromptVia::Arg,
+            prompt_flag: None,
+            session_args: Vec::new(),
+            resume_args: Vec::new(),
+            output: adapter::Extract::Raw,
+        });
+
+        let started = std::time::Instant::now();
+        let err = Timed::wrap(Box::new(smith), 1)
+            .invoke("x")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SlagError::SmithTimeout(1)), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let grandchild = std::fs::read_to_string(&pid_file).unwrap();
+        let alive = || {
+            std::process::Command::new("kill")
+                .args(["-0", grandchild.trim()])
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+        };
+        for _ in 0..100 {
+            if !alive() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive());
+    }
+
     #[test]
     fn detect_questions() {
         assert!(has_questions("What framework should we use?"));
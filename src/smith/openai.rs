@@ -0,0 +1,161 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::{Deserialize, Serialize};
+
+use super::usage::{self, Prices, Reported, Usage};
+use super::{http, Smith};
+use crate::config::OpenAiSettings;
+use crate::error::SlagError;
+
+/// Smith for servers speaking the OpenAI chat-completions protocol
+/// (llama.cpp, vLLM, Ollama). Edits come back as file blocks.
+pub struct OpenAiSmith {
+    settings: OpenAiSettings,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    messages: [Message<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Reported>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl OpenAiSmith {
+    pub fn new(settings: OpenAiSettings, api_key: Option<String>) -> Self {
+        Self {
+            settings,
+            api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Read the key, if any, from the env var named in `api_key_env`
+    pub fn from_settings(settings: &OpenAiSettings) -> Self {
+        let api_key = std::env::var(&settings.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty());
+        Self::new(settings.clone(), api_key)
+    }
+
+    async fn invoke_impl(&self, prompt: &str) -> Result<String, SlagError> {
+        let url = format!(
+            "{}/chat/completions",
+            self.settings.base_url.trim_end_matches('/')
+        );
+        let body = Request {
+            model: &self.settings.model,
+            max_tokens: self.settings.max_tokens,
+            messages: [Message {
+                role: "user",
+                content: prompt,
+            }],
+        };
+
+        let request = self.client.post(&url).json(&body);
+        let response = http::send(match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        })
+        .await?;
+        let parsed: Response = http::json(response).await?;
+        if let Some(reported) = &parsed.usage {
+            usage::report(Usage::priced(
+                reported,
+                Prices {
+                    input: self.settings.input_price,
+                    output: self.settings.output_price,
+                },
+            ));
+        }
+        parsed
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| SlagError::SmithFailed("empty completion".into()))
+    }
+}
+
+impl Smith for OpenAiSmith {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move { self.invoke_impl(&prompt).await })
+    }
+
+    fn edits_files(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smith::http::stand_in;
+
+    fn smith(base_url: String, api_key: Option<&str>) -> OpenAiSmith {
+        let settings = OpenAiSettings {
+            base_url: format!("{base_url}/v1"),
+            model: "qwen".into(),
+            ..OpenAiSettings::default()
+        };
+        OpenAiSmith::new(settings, api_key.map(String::from))
+    }
+
+    const OK: &str =
+        r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"CMD: true"}}]}"#;
+
+    #[tokio::test]
+    async fn posts_chat_completion() {
+        let (url, server) = stand_in::serve(vec![(200, OK)]).await;
+        let out = smith(url, Some("sk-local")).invoke("hi").await.unwrap();
+        assert_eq!(out, "CMD: true");
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /v1/chat/completions "));
+        assert!(requests[0].contains("authorization: Bearer sk-local"));
+        assert!(requests[0].contains(r#""model":"qwen""#));
+    }
+
+    #[tokio::test]
+    async fn key_is_optional() {
+        let (url, server) = stand_in::serve(vec![(200, OK)]).await;
+        smith(url, None).invoke("hi").await.unwrap();
+        assert!(!server.await.unwrap()[0].contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn empty_choices_fail() {
+        let (url, _server) = stand_in::serve(vec![(200, r#"{"choices":[]}"#)]).await;
+        assert!(smith(url, None).invoke("hi").await.is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::{Smith, Tap};
+use crate::config::{settings, TransientSettings};
+use crate::error::SlagError;
+
+/// Exit code agents use for "try again later" (sysexits EX_TEMPFAIL)
+const EX_TEMPFAIL: i32 = 75;
+
+/// Output fragments (lowercased) that mark a failure as the backend's, not the work's
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "rate limit",
+    "rate_limit",
+    "too many requests",
+    "overloaded",
+    "bad gateway",
+    "service unavailable",
+    "temporarily unavailable",
+    "econnreset",
+    "econnrefused",
+    "etimedout",
+    "enotfound",
+    "connection reset",
+    "connection refused",
+    "network error",
+    "socket hang up",
+];
+
+/// HTTP statuses that mean the backend is busy or down
+const TRANSIENT_STATUSES: &[&str] = &["429", "502", "503", "504", "529"];
+
+/// Words that introduce an HTTP status, as in `status 429` or `API Error: 529`
+const STATUS_WORDS: &[&str] = &["status", "code", "http", "error"];
+
+/// Only the end of the output is checked: that is where an agent reports
+/// why it stopped, while earlier lines may quote anything the work printed
+const TAIL_LINES: usize = 5;
+
+/// Error for a smith that exited with `code`: transient when the exit code
+/// or the last lines of output say the backend was unavailable, permanent otherwise
+pub fn classify(code: Option<i32>, output: &str) -> SlagError {
+    let message = format!("exit {}: {}", code.unwrap_or(-1), output.trim());
+    if code == Some(EX_TEMPFAIL) || backend_unavailable(output) {
+        SlagError::SmithTransient(message)
+    } else {
+        SlagError::SmithFailed(message)
+    }
+}
+
+fn backend_unavailable(output: &str) -> bool {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    let tail = lines[lines.len().saturating_sub(TAIL_LINES)..]
+        .join("\n")
+        .to_lowercase();
+    if TRANSIENT_PATTERNS.iter().any(|p| tail.contains(p)) {
+        return true;
+    }
+    let words: Vec<&str> = tail
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    words
+        .windows(2)
+        .any(|w| STATUS_WORDS.contains(&w[0]) && TRANSIENT_STATUSES.contains(&w[1]))
+}
+
+/// Trips after `failures` transient errors in a row from any smith and
+/// holds every smith back for `cooldown`, so a dead backend is not hammered
+/// by all anvils at once. After the cooldown smiths go again; one more
+/// transient failure before a success trips it again.
+pub struct Breaker {
+    failures: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    pub fn new(failures: u32, cooldown: Duration) -> Self {
+        Self {
+            failures: failures.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// The breaker shared by every smith in this run
+    pub fn global() -> &'static Breaker {
+        static GLOBAL: OnceLock<Breaker> = OnceLock::new();
+        GLOBAL.get_or_init(|| {
+            let t = &settings().transient;
+            Breaker::new(
+                t.breaker_failures,
+                Duration::from_secs(t.breaker_cooldown_secs),
+            )
+        })
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait while the breaker is open
+    pub async fn closed(&self) {
+        loop {
+            let open_until = self.state().open_until;
+            match open_until {
+                Some(until) if until > Instant::now() => {
+                    tokio::time::sleep_until(until.into()).await;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    pub fn success(&self) {
+        let mut state = self.state();
+        state.consecutive = 0;
+        state.open_until = None;
+    }
+
+    /// Record a transient failure; true if this one tripped the breaker
+    pub fn failure(&self) -> bool {
+        let mut state = self.state();
+        state.consecutive += 1;
+        let open = state.open_until.is_some_and(|until| until > Instant::now());
+        if state.consecutive >= self.failures && !open {
+            state.open_until = Some(Instant::now() + self.cooldown);
+            return true;
+        }
+        false
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state()
+            .open_until
+            .is_some_and(|until| until > Instant::now())
+    }
+}
+
+/// Retries transient smith failures with exponential backoff and jitter,
+/// behind the circuit breaker. Retries happen inside one invocation, so the
+/// caller's heat count is untouched.
+pub struct Retrying {
+    inner: Box<dyn Smith>,
+    policy: TransientSettings,
+    breaker: &'static Breaker,
+}
+
+impl Retrying {
+    pub fn wrap(
+        smith: Box<dyn Smith>,
+        policy: TransientSettings,
+        breaker: &'static Breaker,
+    ) -> Box<dyn Smith> {
+        Box::new(Retrying {
+            inner: smith,
+            policy,
+            breaker,
+        })
+    }
+
+    async fn run<F, Fut>(&self, attempt: F) -> Result<String, SlagError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<String, SlagError>>,
+    {
+        let mut retry = 0;
+        loop {
+            self.breaker.closed().await;
+            let error = match attempt().await {
+                Ok(out) => {
+                    self.breaker.success();
+                    return Ok(out);
+                }
+                Err(e @ SlagError::SmithTransient(_)) => e,
+                Err(e) => return Err(e),
+            };
+
+            if self.breaker.failure() {
+                println!(
+                    "\n  \x1b[38;5;220m⏸\x1b[0m smith backend looks down, pausing all anvils for {}s \x1b[90m({error})\x1b[0m",
+                    self.policy.breaker_cooldown_secs
+                );
+            }
+            if retry >= self.policy.retries {
+                return Err(error);
+            }
+            let delay = backoff(&self.policy, retry);
+            println!(
+                "    \x1b[90m↻ transient smith failure, retry {}/{} in {:.1}s\x1b[0m",
+                retry + 1,
+                self.policy.retries,
+                delay.as_secs_f64()
+            );
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+}
+
+impl Smith for Retrying {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move {
+            let prompt = prompt.as_str();
+            self.run(|| self.inner.invoke(prompt)).await
+        })
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        Box::pin(self.run(move || self.inner.invoke_tapped(prompt, tap)))
+    }
+
+    fn edits_files(&self) -> bool {
+        self.inner.edits_files()
+    }
+
+    fn sessions(&self) -> bool {
+        self.inner.sessions()
+    }
+}
+
+/// Exponential delay for `retry` (0-based), capped, with "equal jitter":
+/// somewhere between half and all of it, so anvils don't retry in lockstep
+fn backoff(policy: &TransientSettings, retry: u32) -> Duration {
+    let exp = policy
+        .base_delay_ms
+        .saturating_mul(1 << retry.min(16))
+        .min(policy.max_delay_ms);
+    let half = exp / 2;
+    Duration::from_millis(half + jitter(exp - half))
+}
+
+/// Cheap uniform-ish value in `0..=max` from the clock and a counter
+fn jitter(max: u64) -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    if max == 0 {
+        return 0;
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    // splitmix64 finalizer
+    let mut x = nanos ^ SEQ.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    x % (max + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fails with the given errors in order, then answers "ok"
+    struct Flaky {
+        errors: Mutex<Vec<SlagError>>,
+        calls: AtomicUsize,
+    }
+
+    impl Flaky {
+        fn new(errors: Vec<SlagError>) -> Self {
+            Self {
+                errors: Mutex::new(errors),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl Smith for Flaky {
+        fn invoke(
+            &self,
+            _prompt: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let mut errors = self.errors.lock().unwrap();
+            let result = if errors.is_empty() {
+                Ok("ok".to_string())
+            } else {
+                Err(errors.remove(0))
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    fn policy(retries: u32) -> TransientSettings {
+        TransientSettings {
+            retries,
+            base_delay_ms: 1,
+            max_delay_ms: 4,
+            breaker_failures: 100,
+            breaker_cooldown_secs: 1,
+        }
+    }
+
+    fn transient() -> SlagError {
+        SlagError::SmithTransient("exit 1: overloaded".into())
+    }
+
+    fn leak(breaker: Breaker) -> &'static Breaker {
+        Box::leak(Box::new(breaker))
+    }
+
+    #[test]
+    fn classifies_by_exit_code_and_output() {
+        assert!(matches!(
+            classify(Some(1), "API Error: 529 Overloaded"),
+            SlagError::SmithTransient(_)
+        ));
+        assert!(matches!(
+            classify(Some(1), "Error: Rate limit reached"),
+            SlagError::SmithTransient(_)
+        ));
+        assert!(matches!(
+            classify(Some(75), ""),
+            SlagError::SmithTransient(_)
+        ));
+        assert!(matches!(
+            classify(Some(1), "error[E0425]: cannot find value"),
+            SlagError::SmithFailed(_)
+        ));
+    }
+
+    #[test]
+    fn status_codes_count_only_as_statuses_near_the_end() {
+        assert!(matches!(
+            classify(Some(1), "request failed with status 503"),
+            SlagError::SmithTransient(_)
+        ));
+        assert!(matches!(
+            classify(Some(1), "HTTP error 429"),
+            SlagError::SmithTransient(_)
+        ));
+        assert!(matches!(
+            classify(Some(1), "error at src/lib.rs:429"),
+            SlagError::SmithFailed(_)
+        ));
+        assert!(matches!(
+            classify(Some(1), "listening on port 5030; commit a529bc1 failed"),
+            SlagError::SmithFailed(_)
+        ));
+        // A rate limit quoted early in a long transcript is not why it stopped
+        let transcript = format!(
+            "docs mention rate limit handling\n{}",
+            "test failed\n".repeat(6)
+        );
+        assert!(matches!(
+            classify(Some(1), &transcript),
+            SlagError::SmithFailed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_only() {
+        let breaker = leak(Breaker::new(100, Duration::from_secs(1)));
+        let flaky = std::sync::Arc::new(Flaky::new(vec![transient(), transient()]));
+
+        struct Shared(std::sync::Arc<Flaky>);
+        impl Smith for Shared {
+            fn invoke(
+                &self,
+                prompt: &str,
+            ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+                self.0.invoke(prompt)
+            }
+        }
+
+        let smith = Retrying::wrap(Box::new(Shared(flaky.clone())), policy(3), breaker);
+        assert_eq!(smith.invoke("x").await.unwrap(), "ok");
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        let permanent = Flaky::new(vec![SlagError::SmithFailed("exit 1: bad".into())]);
+        let smith = Retrying::wrap(Box::new(permanent), policy(3), breaker);
+        assert!(matches!(
+            smith.invoke("x").await,
+            Err(SlagError::SmithFailed(_))
+        ));
+
+        let exhausted = Flaky::new(vec![transient(), transient(), transient()]);
+        let smith = Retrying::wrap(Box::new(exhausted), policy(1), breaker);
+        assert!(matches!(
+            smith.invoke("x").await,
+            Err(SlagError::SmithTransient(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn breaker_pauses_then_probes() {
+        let breaker = leak(Breaker::new(2, Duration::from_millis(300)));
+        assert!(!breaker.failure());
+        assert!(breaker.failure());
+        assert!(breaker.is_open());
+
+        let started = Instant::now();
+        let smith = Retrying::wrap(Box::new(Flaky::new(vec![])), policy(0), breaker);
+        assert_eq!(smith.invoke("x").await.unwrap(), "ok");
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn backoff_grows_with_jitter_and_cap() {
+        let p = TransientSettings {
+            base_delay_ms: 1000,
+            max_delay_ms: 5000,
+            ..policy(5)
+        };
+        for _ in 0..50 {
+            let first = backoff(&p, 0).as_millis();
+            assert!((500..=1000).contains(&first), "{first}");
+            let capped = backoff(&p, 10).as_millis();
+            assert!((2500..=5000).contains(&capped), "{capped}");
+        }
+    }
+}
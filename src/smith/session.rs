@@ -0,0 +1,71 @@
+use std::hash::BuildHasher;
+
+/// One agent conversation kept across the heats of an ingot. Smiths that
+/// support sessions (see `Smith::sessions`) start it on the first call and
+/// continue it afterwards, so later heats only need to say what changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub id: String,
+    /// Continue the conversation rather than start it
+    pub resume: bool,
+}
+
+impl Session {
+    /// A new conversation under a fresh id
+    pub fn start() -> Self {
+        Self {
+            id: new_id(),
+            resume: false,
+        }
+    }
+
+    /// Continue the conversation `id`, e.g. one left by an earlier run
+    pub fn resume(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            resume: true,
+        }
+    }
+}
+
+/// Random version 4 UUID, the form claude's `--session-id` takes
+pub fn new_id() -> String {
+    // Every RandomState is seeded afresh, which is random enough for an id
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let high = std::collections::hash_map::RandomState::new().hash_one(nanos);
+    let low = std::collections::hash_map::RandomState::new().hash_one(nanos);
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&high.to_be_bytes());
+    bytes[8..].copy_from_slice(&low.to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_distinct_v4_uuids() {
+        let id = new_id();
+        let groups: Vec<usize> = id.split('-').map(str::len).collect();
+        assert_eq!(groups, [8, 4, 4, 4, 12], "{id}");
+        assert!(id.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert_eq!(&id[14..15], "4");
+        assert!("89ab".contains(&id[19..20]), "{id}");
+        assert_ne!(new_id(), new_id());
+    }
+}
@@ -0,0 +1,290 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::Write;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{paths, project_path, Phase};
+
+/// Tokens and money spent by smith calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Usage as APIs and agents report it: the Messages API and claude's JSON
+/// output use `input_tokens`, chat completions use `prompt_tokens`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Reported {
+    #[serde(alias = "prompt_tokens")]
+    pub input_tokens: u64,
+    #[serde(alias = "completion_tokens")]
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+}
+
+/// USD per million tokens; cache reads bill at a tenth of input, cache writes at 1.25x
+#[derive(Debug, Clone, Copy)]
+pub struct Prices {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl Usage {
+    /// Reported tokens with the cost the backend itself stated
+    pub fn stated(reported: &Reported, cost_usd: f64) -> Self {
+        Self {
+            input_tokens: reported.input_tokens,
+            output_tokens: reported.output_tokens,
+            cache_read_tokens: reported.cache_read_input_tokens,
+            cache_write_tokens: reported.cache_creation_input_tokens,
+            cost_usd,
+        }
+    }
+
+    /// Reported tokens, costed at `prices`
+    pub fn priced(reported: &Reported, prices: Prices) -> Self {
+        let mut usage = Self::stated(reported, 0.0);
+        let input = usage.input_tokens as f64
+            + usage.cache_read_tokens as f64 * 0.1
+            + usage.cache_write_tokens as f64 * 1.25;
+        usage.cost_usd = (input * prices.input + usage.output_tokens as f64 * prices.output) / 1e6;
+        usage
+    }
+
+    pub fn add(&mut self, other: Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Usage::default()
+    }
+
+    /// `12.3k in · 4.1k out · $0.42`
+    pub fn summary(&self) -> String {
+        let input = self.input_tokens + self.cache_read_tokens + self.cache_write_tokens;
+        format!(
+            "{} in · {} out · ${:.2}",
+            tokens(input),
+            tokens(self.output_tokens),
+            self.cost_usd
+        )
+    }
+}
+
+fn tokens(n: u64) -> String {
+    if n >= 1_000_000 {
+        format!("{:.1}M", n as f64 / 1e6)
+    } else if n >= 1000 {
+        format!("{:.1}k", n as f64 / 1e3)
+    } else {
+        n.to_string()
+    }
+}
+
+/// What a metered call is charged to
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub phase: Phase,
+    pub ingot: Option<String>,
+    pub heat: Option<u8>,
+}
+
+impl Scope {
+    pub fn phase(phase: Phase) -> Self {
+        Self {
+            phase,
+            ingot: None,
+            heat: None,
+        }
+    }
+
+    pub fn heat(ingot: &str, heat: u8) -> Self {
+        Self {
+            phase: Phase::Forge,
+            ingot: Some(ingot.to_string()),
+            heat: Some(heat),
+        }
+    }
+}
+
+/// Everything spent in this run, per phase and per ingot
+#[derive(Debug, Default)]
+pub struct Tally {
+    pub total: Usage,
+    pub phases: BTreeMap<Phase, Usage>,
+    pub ingots: BTreeMap<String, Usage>,
+}
+
+static TALLY: Mutex<Tally> = Mutex::new(Tally {
+    total: Usage {
+        input_tokens: 0,
+        output_tokens: 0,
+        cache_read_tokens: 0,
+        cache_write_tokens: 0,
+        cost_usd: 0.0,
+    },
+    phases: BTreeMap::new(),
+    ingots: BTreeMap::new(),
+});
+
+tokio::task_local! {
+    /// Usage reported by smiths inside the innermost `metered` call
+    static CURRENT: Cell<Usage>;
+}
+
+/// Called by smiths with what one backend call used. Outside `metered` it is dropped.
+pub fn report(usage: Usage) {
+    let _ = CURRENT.try_with(|current| {
+        let mut sum = current.get();
+        sum.add(usage);
+        current.set(sum);
+    });
+}
+
+/// Run a smith call, charging whatever it reports to `scope`: the run's
+/// tally and a line in `usage.log`. Returns the call's output and its usage.
+pub async fn metered<T>(scope: Scope, call: impl Future<Output = T>) -> (T, Usage) {
+    let (output, usage) = CURRENT
+        .scope(Cell::new(Usage::default()), async {
+            let output = call.await;
+            (output, CURRENT.with(Cell::get))
+        })
+        .await;
+    if !usage.is_empty() {
+        record(&scope, usage);
+        log(&scope, usage);
+    }
+    (output, usage)
+}
+
+fn tally() -> std::sync::MutexGuard<'static, Tally> {
+    TALLY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn record(scope: &Scope, usage: Usage) {
+    let mut tally = tally();
+    tally.total.add(usage);
+    tally.phases.entry(scope.phase).or_default().add(usage);
+    if let Some(ingot) = &scope.ingot {
+        tally.ingots.entry(ingot.clone()).or_default().add(usage);
+    }
+}
+
+fn log(scope: &Scope, usage: Usage) {
+    let line = format!(
+        "{} {} {} {} in={} out={} cache_read={} cache_write={} cost=${:.4}\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        phase_name(scope.phase),
+        scope.ingot.as_deref().unwrap_or("-"),
+        scope.heat.map_or("-".to_string(), |h| h.to_string()),
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_tokens,
+        usage.cache_write_tokens,
+        usage.cost_usd,
+    );
+    // The log dir exists once the furnace is fired
+    let _ = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(project_path(&paths().log_dir).join("usage.log"))
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+}
+
+/// As spelled in `[smith.phases]`
+fn phase_name(phase: Phase) -> String {
+    format!("{phase:?}").to_lowercase()
+}
+
+/// Total spent so far in this run, in USD
+pub fn spent() -> f64 {
+    tally().total.cost_usd
+}
+
+/// Whether a budget (USD, 0 = none) has been used up
+pub fn over_budget(budget: f64) -> bool {
+    budget > 0.0 && spent() >= budget
+}
+
+/// Spend so far for one ingot
+pub fn for_ingot(id: &str) -> Usage {
+    tally().ingots.get(id).copied().unwrap_or_default()
+}
+
+/// Per-phase lines plus the total, empty if nothing reported usage
+pub fn summary_lines() -> Vec<String> {
+    let tally = tally();
+    if tally.total.is_empty() {
+        return Vec::new();
+    }
+    let mut lines: Vec<String> = tally
+        .phases
+        .iter()
+        .map(|(phase, usage)| format!("{}: {}", phase_name(*phase), usage.summary()))
+        .collect();
+    lines.push(format!("total: {}", tally.total.summary()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_reported_tokens() {
+        let reported: Reported = serde_json::from_str(
+            r#"{"input_tokens":1000000,"output_tokens":100000,"cache_read_input_tokens":1000000}"#,
+        )
+        .unwrap();
+        let usage = Usage::priced(
+            &reported,
+            Prices {
+                input: 3.0,
+                output: 15.0,
+            },
+        );
+        // 3.00 input + 0.30 cache reads + 1.50 output
+        assert!((usage.cost_usd - 4.8).abs() < 1e-9, "{}", usage.cost_usd);
+        assert_eq!(usage.summary(), "2.0M in · 100.0k out · $4.80");
+
+        let chat: Reported =
+            serde_json::from_str(r#"{"prompt_tokens":12,"completion_tokens":5}"#).unwrap();
+        assert_eq!((chat.input_tokens, chat.output_tokens), (12, 5));
+    }
+
+    #[tokio::test]
+    async fn metered_scopes_collect_their_own_reports() {
+        let one = Usage {
+            output_tokens: 7,
+            cost_usd: 0.5,
+            ..Usage::default()
+        };
+        let (_, outer) = metered(Scope::heat("usage-test", 1), async {
+            report(one);
+            // A nested scope keeps what is reported inside it
+            let (_, inner) = metered(Scope::phase(Phase::Resmelt), async { report(one) }).await;
+            assert_eq!(inner, one);
+            report(one);
+        })
+        .await;
+        assert_eq!(outer.output_tokens, 14);
+        assert_eq!(for_ingot("usage-test").output_tokens, 14);
+
+        // Reports outside any scope go nowhere
+        report(one);
+        assert_eq!(for_ingot("usage-test").cost_usd, 1.0);
+    }
+}
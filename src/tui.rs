@@ -96,15 +96,16 @@ pub fn temper_bar(counts: &CrucibleCounts) {
     println!("{}]{} {}{}%{}", fg(COLD), reset(), fg(PURE), pct, reset());
 }
 
-/// Create a spinner for long operations
+/// Create a spinner for long operations; `set_message` replaces `msg`
 pub fn spinner(msg: &str) -> ProgressBar {
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::default_spinner()
             .tick_chars("◐◓◑◒ ")
-            .template(&format!("   {{spinner}} {msg}"))
+            .template("   {spinner} {msg}")
             .unwrap(),
     );
+    pb.set_message(msg.to_string());
     pb.enable_steady_tick(std::time::Duration::from_millis(150));
     pb
 }
@@ -123,10 +124,9 @@ pub fn spark_spinner(msg: &str) -> ProgressBar {
 }
 
 pub fn truncate(s: &str, max: usize) -> String {
-    if s.len() > max {
-        format!("{}...", &s[..max])
-    } else {
-        s.to_string()
+    match s.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &s[..end]),
+        None => s.to_string(),
     }
 }
 
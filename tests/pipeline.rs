@@ -0,0 +1,737 @@
+//! Full commissions run end to end in temp git repos, with scripted smiths
+//! standing in for the agents.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use slag::anvil::worktree;
+use slag::config::{paths, project_path, Phase, PipelineConfig};
+use slag::crucible::Crucible;
+use slag::error::SlagError;
+use slag::pipeline;
+use slag::sexp::{Ingot, Status, SESSION_FIELD};
+use slag::shutdown;
+use slag::smith::mock::MockSmith;
+use slag::smith::session::Session;
+use slag::smith::usage::{self, Usage};
+use slag::smith::{Smith, SmithFactory, Tap};
+
+/// The pipeline works in the current directory, so tests take turns
+static CWD: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Prompts a smith was sent, each with the session it was sent in
+type PromptLog = Arc<Mutex<Vec<(String, Option<Session>)>>>;
+
+/// Smiths that answer from a script: one reply per phase, and per ingot
+/// the replies to its heats in order. Unscripted calls fail.
+#[derive(Default)]
+struct Script {
+    phases: BTreeMap<Phase, String>,
+    strikes: BTreeMap<String, Vec<String>>,
+    asked: Mutex<Vec<String>>,
+    /// When set, forge smiths keep sessions and log each prompt with its session
+    sessions: Option<PromptLog>,
+    /// USD each forge smith call reports spending
+    cost: f64,
+    /// When set, forge smiths take their time and log when they run
+    pace: Option<Arc<Pace>>,
+    /// Ingot whose smith asks for a graceful stop once its strike is over
+    stop_after: Option<String>,
+}
+
+impl Script {
+    fn on(mut self, phase: Phase, reply: &str) -> Self {
+        self.phases.insert(phase, reply.to_string());
+        self
+    }
+
+    fn strike(mut self, id: &str, replies: &[&str]) -> Self {
+        let replies = replies.iter().map(|r| r.to_string()).collect();
+        self.strikes.insert(id.to_string(), replies);
+        self
+    }
+
+    fn asked(&self) -> Vec<String> {
+        self.asked.lock().unwrap().clone()
+    }
+}
+
+impl SmithFactory for Script {
+    fn phase(&self, phase: Phase) -> Box<dyn Smith> {
+        self.asked.lock().unwrap().push(format!("{phase:?}"));
+        match self.phases.get(&phase) {
+            Some(reply) => Box::new(MockSmith::fixed(reply)),
+            None => Box::new(MockSmith::failing()),
+        }
+    }
+
+    fn forge(&self, ingot: &Ingot) -> Box<dyn Smith> {
+        self.asked
+            .lock()
+            .unwrap()
+            .push(format!("forge {} gr:{}", ingot.id, ingot.grade));
+        let replies = self.strikes.get(&ingot.id).cloned().unwrap_or_default();
+        let smith = MockSmith::new(replies);
+        let smith: Box<dyn Smith> = match &self.sessions {
+            Some(log) => Box::new(Sessioned {
+                inner: smith,
+                log: log.clone(),
+            }),
+            None => Box::new(smith),
+        };
+        if self.stop_after.as_ref() == Some(&ingot.id) {
+            return Box::new(StopsWhenDone(smith));
+        }
+        if let Some(pace) = &self.pace {
+            return Box::new(Paced {
+                inner: smith,
+                id: ingot.id.clone(),
+                pace: pace.clone(),
+            });
+        }
+        if self.cost > 0.0 {
+            return Box::new(Charged {
+                inner: smith,
+                cost: self.cost,
+            });
+        }
+        smith
+    }
+}
+
+/// How long each ingot's strikes take, and a record of when they ran
+#[derive(Default)]
+struct Pace {
+    millis: BTreeMap<String, u64>,
+    events: Mutex<Vec<String>>,
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl Pace {
+    fn new(millis: &[(&str, u64)]) -> Arc<Self> {
+        Arc::new(Pace {
+            millis: millis
+                .iter()
+                .map(|(id, ms)| (id.to_string(), *ms))
+                .collect(),
+            ..Pace::default()
+        })
+    }
+
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn position(&self, event: &str) -> usize {
+        let events = self.events();
+        events
+            .iter()
+            .position(|e| e == event)
+            .unwrap_or_else(|| panic!("no {event} in {events:?}"))
+    }
+}
+
+/// A smith that takes its ingot's time from a `Pace`
+struct Paced {
+    inner: Box<dyn Smith>,
+    id: String,
+    pace: Arc<Pace>,
+}
+
+impl Smith for Paced {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        let prompt = prompt.to_string();
+        Box::pin(async move {
+            let pace = &self.pace;
+            pace.events
+                .lock()
+                .unwrap()
+                .push(format!("start {}", self.id));
+            let running = pace.running.fetch_add(1, Ordering::SeqCst) + 1;
+            pace.peak.fetch_max(running, Ordering::SeqCst);
+
+            let millis = pace.millis.get(&self.id).copied().unwrap_or(0);
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+
+            pace.running.fetch_sub(1, Ordering::SeqCst);
+            pace.events.lock().unwrap().push(format!("end {}", self.id));
+            self.inner.invoke(&prompt).await
+        })
+    }
+}
+
+/// A smith that requests a graceful stop, like a Ctrl-C, when the anvil
+/// drops it: the stop arrives just as its ingot finishes
+struct StopsWhenDone(Box<dyn Smith>);
+
+impl Smith for StopsWhenDone {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        self.0.invoke(prompt)
+    }
+}
+
+impl Drop for StopsWhenDone {
+    fn drop(&mut self) {
+        shutdown::request();
+    }
+}
+
+/// A smith that reports spending `cost` on every call
+struct Charged {
+    inner: Box<dyn Smith>,
+    cost: f64,
+}
+
+impl Smith for Charged {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        usage::report(Usage {
+            cost_usd: self.cost,
+            ..Usage::default()
+        });
+        self.inner.invoke(prompt)
+    }
+}
+
+/// A scripted smith that supports sessions
+struct Sessioned {
+    inner: MockSmith,
+    log: PromptLog,
+}
+
+impl Smith for Sessioned {
+    fn invoke(
+        &self,
+        prompt: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + '_>> {
+        self.log.lock().unwrap().push((prompt.to_string(), None));
+        self.inner.invoke(prompt)
+    }
+
+    fn invoke_tapped<'a>(
+        &'a self,
+        prompt: &'a str,
+        tap: &'a Tap,
+    ) -> Pin<Box<dyn Future<Output = Result<String, SlagError>> + Send + 'a>> {
+        let entry = (prompt.to_string(), tap.session.clone());
+        self.log.lock().unwrap().push(entry);
+        self.inner.invoke(prompt)
+    }
+
+    fn sessions(&self) -> bool {
+        true
+    }
+}
+
+/// An empty project directory, current for as long as the workshop lives
+struct Workshop {
+    _turn: tokio::sync::MutexGuard<'static, ()>,
+    root: tempfile::TempDir,
+    previous: PathBuf,
+}
+
+impl Workshop {
+    async fn open() -> Self {
+        let turn = CWD.lock().await;
+        let root = tempfile::tempdir().unwrap();
+        // Worktrees go next to the project, so keep the project one level down
+        let project = root.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        let previous = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&project).unwrap();
+        for (key, value) in [
+            ("GIT_AUTHOR_NAME", "slag"),
+            ("GIT_AUTHOR_EMAIL", "slag@example.com"),
+            ("GIT_COMMITTER_NAME", "slag"),
+            ("GIT_COMMITTER_EMAIL", "slag@example.com"),
+        ] {
+            std::env::set_var(key, value);
+        }
+        Self {
+            _turn: turn,
+            root,
+            previous,
+        }
+    }
+
+    fn project(&self) -> PathBuf {
+        self.root.path().join("project")
+    }
+
+    fn crucible(&self) -> Crucible {
+        Crucible::load(&project_path(&paths().crucible)).unwrap()
+    }
+
+    /// Run git in the project, failing the test if it fails
+    fn git(&self, args: &[&str]) -> String {
+        let out = std::process::Command::new("git")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "git {args:?}: {out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    }
+
+    /// A repo with one commit, for worktrees to branch from
+    fn init_repo(&self) {
+        self.git(&["init", "-q"]);
+        self.git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+    }
+
+    fn git_log(&self) -> String {
+        let out = std::process::Command::new("git")
+            .args(["log", "--format=%s"])
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    }
+}
+
+impl Drop for Workshop {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.previous);
+    }
+}
+
+const BLUEPRINT: &str = "# Blueprint\n\nTwo files, written by shell commands.\n";
+
+fn ingot(id: &str, proof: &str) -> String {
+    format!(
+        "(ingot :id \"{id}\" :status ore :solo t :grade 1 :heat 0 :max 2 :proof \"{proof}\" :work \"Write {id}\")"
+    )
+}
+
+fn config(anvils: usize) -> PipelineConfig {
+    PipelineConfig {
+        max_anvils: anvils,
+        ..PipelineConfig::default()
+    }
+}
+
+fn status(crucible: &Crucible, id: &str) -> Option<Status> {
+    crucible.get(id).map(|i| i.status.clone())
+}
+
+#[tokio::test]
+async fn commission_runs_survey_to_assay() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(
+            Phase::Found,
+            &format!(
+                "{}\n{}\n",
+                ingot("a", "test -f a.txt"),
+                ingot("b", "grep -q hi b.txt")
+            ),
+        )
+        .strike("a", &["CMD: echo hello > a.txt"])
+        // First heat misses, the second forges
+        .strike("b", &["CMD: false", "CMD: echo hi > b.txt"]);
+
+    // One anvil, so each forge commit holds just its own ingot's work
+    pipeline::run(Some("Write two files"), &script, &config(1))
+        .await
+        .unwrap();
+
+    assert!(shop.project().join("a.txt").exists());
+    assert!(shop.project().join("b.txt").exists());
+    let crucible = shop.crucible();
+    assert_eq!(status(&crucible, "a"), Some(Status::Forged));
+    assert_eq!(crucible.get("b").unwrap().heat, 2);
+
+    let blueprint = std::fs::read_to_string(project_path(&paths().blueprint)).unwrap();
+    assert_eq!(blueprint, BLUEPRINT);
+    let log = shop.git_log();
+    assert!(log.contains("forge(a): Write a"), "{log}");
+    assert!(log.contains("forge(b): Write b"), "{log}");
+
+    let asked = script.asked();
+    assert_eq!(asked[..2], ["Survey", "Found"]);
+    assert!(asked.contains(&"forge a gr:1".to_string()), "{asked:?}");
+
+    // Slag's private files are ignored from inside the state directory
+    assert!(!shop.project().join(".gitignore").exists());
+    let ignored = std::fs::read_to_string(project_path(".gitignore")).unwrap();
+    assert_eq!(ignored, "run.lock\nlogs/\n");
+}
+
+#[tokio::test]
+async fn report_block_drives_the_strike() {
+    let shop = Workshop::open().await;
+    let report = "Wrote it.\n```json\n\
+        {\"cmd\": \"echo hi > a.txt\", \"files_touched\": [\"a.txt\"],\n\
+         \"learnings\": \"echo is enough here\", \"confidence\": 0.9}\n```\n";
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &ingot("a", "grep -q hi a.txt"))
+        .strike("a", &[report]);
+
+    pipeline::run(Some("Write a file"), &script, &config(1))
+        .await
+        .unwrap();
+
+    assert_eq!(status(&shop.crucible(), "a"), Some(Status::Forged));
+    let ledger = std::fs::read_to_string(project_path(&paths().ledger)).unwrap();
+    assert!(ledger.contains("- files: a.txt\n"), "{ledger}");
+    assert!(
+        ledger.contains("- learned: echo is enough here\n"),
+        "{ledger}"
+    );
+}
+
+#[tokio::test]
+async fn freed_anvil_takes_the_next_ingot_at_once() {
+    let shop = Workshop::open().await;
+    let pace = Pace::new(&[("slow", 400), ("quick", 20), ("next", 20)]);
+    let script = Script {
+        pace: Some(pace.clone()),
+        ..Script::default()
+    }
+    .on(Phase::Survey, BLUEPRINT)
+    .on(
+        Phase::Found,
+        &[
+            ingot("slow", "true"),
+            ingot("quick", "true"),
+            ingot("next", "true"),
+        ]
+        .join("\n"),
+    )
+    .strike("slow", &["CMD: true"])
+    .strike("quick", &["CMD: true"])
+    .strike("next", &["CMD: true"]);
+
+    pipeline::run(Some("Three jobs"), &script, &config(2))
+        .await
+        .unwrap();
+
+    // `next` took quick's anvil while slow was still being struck
+    assert!(pace.position("start next") > pace.position("end quick"));
+    assert!(pace.position("start next") < pace.position("end slow"));
+    assert_eq!(status(&shop.crucible(), "next"), Some(Status::Forged));
+}
+
+#[tokio::test]
+async fn pool_never_runs_more_than_its_anvils() {
+    let shop = Workshop::open().await;
+    let ids = ["p1", "p2", "p3", "p4", "p5", "p6"];
+    let millis: Vec<(&str, u64)> = ids
+        .iter()
+        .zip([30, 10, 50, 20, 10, 40])
+        .map(|(id, ms)| (*id, ms))
+        .collect();
+    let pace = Pace::new(&millis);
+    let mut script = Script {
+        pace: Some(pace.clone()),
+        ..Script::default()
+    }
+    .on(Phase::Survey, BLUEPRINT)
+    .on(Phase::Found, &ids.map(|id| ingot(id, "true")).join("\n"));
+    for id in ids {
+        script = script.strike(id, &["CMD: true"]);
+    }
+
+    pipeline::run(Some("Six jobs"), &script, &config(3))
+        .await
+        .unwrap();
+
+    assert_eq!(pace.peak.load(Ordering::SeqCst), 3);
+    assert_eq!(pace.events().len(), 2 * ids.len());
+    let crucible = shop.crucible();
+    assert!(ids
+        .iter()
+        .all(|id| status(&crucible, id) == Some(Status::Forged)));
+}
+
+#[tokio::test]
+async fn cracked_ingot_is_resmelted_into_replacements() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &ingot("big", "test -f big.txt"))
+        .strike("big", &["CMD: false"])
+        .on(
+            Phase::Resmelt,
+            &format!(
+                "{}\n{}\n",
+                ingot("big1", "test -f x.txt"),
+                ingot("big2", "test -f y.txt")
+            ),
+        )
+        .strike("big1", &["CMD: touch x.txt"])
+        .strike("big2", &["CMD: touch y.txt"]);
+
+    pipeline::run(Some("One big job"), &script, &config(1))
+        .await
+        .unwrap();
+
+    let crucible = shop.crucible();
+    assert_eq!(status(&crucible, "big"), None);
+    assert_eq!(status(&crucible, "big1"), Some(Status::Forged));
+    assert_eq!(status(&crucible, "big2"), Some(Status::Forged));
+    assert!(shop.project().join("y.txt").exists());
+    assert!(script.asked().contains(&"Resmelt".to_string()));
+}
+
+#[tokio::test]
+async fn later_heats_continue_the_ingot_session() {
+    let shop = Workshop::open().await;
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let script = Script {
+        sessions: Some(log.clone()),
+        ..Script::default()
+    }
+    .on(Phase::Survey, BLUEPRINT)
+    .on(Phase::Found, &ingot("a", "test -f a.txt"))
+    .strike("a", &["CMD: false", "CMD: touch a.txt"]);
+    let pipeline_config = PipelineConfig {
+        sessions: true,
+        ..config(1)
+    };
+
+    pipeline::run(Some("Write a file"), &script, &pipeline_config)
+        .await
+        .unwrap();
+
+    let log = log.lock().unwrap();
+    let [(first, Some(start)), (second, Some(resume))] = &log[..] else {
+        panic!("{log:?}");
+    };
+    assert!(!start.resume && first.contains("=== FORGE ORDER ==="));
+    assert!(resume.resume && resume.id == start.id);
+    assert!(second.starts_with("=== HEAT 2/2 [a] ==="), "{second}");
+    assert!(second.contains("CMD failed") && !second.contains("BLUEPRINT"));
+
+    let a = shop.crucible().get("a").cloned().unwrap();
+    assert_eq!(a.extra_field(SESSION_FIELD), Some(start.id.as_str()));
+}
+
+#[tokio::test]
+async fn worktree_branches_merge_after_review() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &ingot("a", "test -f a.txt"))
+        .strike("a", &["CMD: echo hello > a.txt"])
+        .on(Phase::Review, "STATUS: APPROVED\nLooks right.");
+    let pipeline_config = PipelineConfig {
+        worktree: true,
+        // The scratch project has no cargo CI to pass
+        review_all: true,
+        ..config(1)
+    };
+
+    pipeline::run(Some("Write a file"), &script, &pipeline_config)
+        .await
+        .unwrap();
+
+    assert!(shop.project().join("a.txt").exists());
+    assert!(!Path::new("../slag-anvil-a").exists());
+    assert!(script.asked().contains(&"Review".to_string()));
+}
+
+/// An ingot struck as `fanout` candidates at once
+fn fanned(id: &str, proof: &str, fanout: u8) -> String {
+    ingot(id, proof).replace(":grade 1", &format!(":grade 1 :fanout {fanout}"))
+}
+
+#[tokio::test]
+async fn first_passing_candidate_is_kept() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &fanned("a", "grep -q hi a.txt", 2))
+        // One reply per candidate: the first misses, the second forges
+        .strike("a", &["CMD: false", "CMD: echo hi > a.txt"]);
+
+    pipeline::run(Some("Write a file"), &script, &config(1))
+        .await
+        .unwrap();
+
+    assert!(shop.project().join("a.txt").exists());
+    let crucible = shop.crucible();
+    assert_eq!(status(&crucible, "a"), Some(Status::Forged));
+    assert_eq!(crucible.get("a").unwrap().heat, 1);
+    assert!(shop.git_log().contains("forge(a): Write a"));
+    for dir in ["../slag-anvil-a", "../slag-anvil-a.1", "../slag-anvil-a.2"] {
+        assert!(!Path::new(dir).exists(), "{dir}");
+    }
+}
+
+#[tokio::test]
+async fn reviewer_picks_among_passing_candidates() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &fanned("a", "test -f a.txt", 2))
+        .strike("a", &["CMD: echo one > a.txt", "CMD: echo two > a.txt"])
+        .on(Phase::Review, "PICK: 2\nREASON: Says two.");
+    let pipeline_config = PipelineConfig {
+        fanout_pick: true,
+        ..config(1)
+    };
+
+    pipeline::run(Some("Write a file"), &script, &pipeline_config)
+        .await
+        .unwrap();
+
+    let written = std::fs::read_to_string(shop.project().join("a.txt")).unwrap();
+    assert_eq!(written, "two\n");
+    assert!(script.asked().contains(&"Review".to_string()));
+}
+
+#[tokio::test]
+async fn interrupted_candidates_are_discarded() {
+    let shop = Workshop::open().await;
+    shop.init_repo();
+    for slot in ["a.1", "a.2", "a", "ab.1"] {
+        worktree::create(slot).await.unwrap();
+    }
+    assert_eq!(worktree::candidate_slots("a").await, ["a.1", "a.2"]);
+
+    worktree::discard_with_candidates("a").await;
+
+    let branches = shop.git(&["branch", "--list", "forge/*", "--format=%(refname:short)"]);
+    assert_eq!(branches.trim(), "forge/ab.1");
+    for dir in ["../slag-anvil-a", "../slag-anvil-a.1", "../slag-anvil-a.2"] {
+        assert!(!Path::new(dir).exists(), "{dir}");
+    }
+}
+
+#[tokio::test]
+async fn adopting_replaces_a_stale_ingot_branch() {
+    let shop = Workshop::open().await;
+    shop.init_repo();
+    // Left behind by a run that died after adopting
+    worktree::create("a").await.unwrap();
+    worktree::create("a.2").await.unwrap();
+    std::fs::write("../slag-anvil-a.2/won.txt", "yes").unwrap();
+    std::process::Command::new("git")
+        .args(["-C", "../slag-anvil-a.2", "add", "-A"])
+        .status()
+        .unwrap();
+    std::process::Command::new("git")
+        .args(["-C", "../slag-anvil-a.2", "commit", "-qm", "win"])
+        .status()
+        .unwrap();
+
+    let dir = worktree::adopt("a.2", "a").await.unwrap();
+
+    assert!(Path::new(&dir).join("won.txt").exists());
+    assert!(!Path::new("../slag-anvil-a.2").exists());
+    let branches = shop.git(&["branch", "--list", "forge/*", "--format=%(refname:short)"]);
+    assert_eq!(branches.trim(), "forge/a");
+    assert_eq!(
+        shop.git(&["log", "-1", "--format=%s", "forge/a"]).trim(),
+        "win"
+    );
+}
+
+#[tokio::test]
+async fn worktrees_share_the_git_directory() {
+    let shop = Workshop::open().await;
+    shop.init_repo();
+    worktree::create("w1").await.unwrap();
+
+    // Where the sandbox lets a worktree's commits be written
+    let git_dir = std::fs::canonicalize(shop.project().join(".git")).unwrap();
+    assert_eq!(worktree::common_dir().await, Some(git_dir));
+    worktree::discard("w1").await;
+}
+
+#[tokio::test]
+async fn cracked_run_reports_failure() {
+    let shop = Workshop::open().await;
+    let script = Script::default()
+        .on(Phase::Survey, BLUEPRINT)
+        .on(Phase::Found, &ingot("a", "test -f a.txt"))
+        .strike("a", &["no command here"])
+        .on(Phase::Resmelt, "IMPOSSIBLE: nothing to write with");
+
+    let err = pipeline::run(Some("Write a file"), &script, &config(1))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SlagError::ForgeFailed(1)), "{err}");
+    assert_eq!(status(&shop.crucible(), "a"), Some(Status::Cracked));
+}
+
+#[tokio::test]
+async fn ingot_failing_once_the_budget_is_spent_goes_back_to_ore() {
+    let shop = Workshop::open().await;
+    let script = Script {
+        cost: 1.0,
+        ..Script::default()
+    }
+    .on(Phase::Survey, BLUEPRINT)
+    .on(Phase::Found, &ingot("a", "test -f a.txt"))
+    .strike("a", &["CMD: false", "CMD: false"]);
+    // Both heats start under budget; the second one spends past it
+    let budget = PipelineConfig {
+        budget: usage::spent() + 1.5,
+        ..config(1)
+    };
+
+    let err = pipeline::run(Some("Write a file"), &script, &budget)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SlagError::BudgetSpent { .. }), "{err}");
+    assert_eq!(status(&shop.crucible(), "a"), Some(Status::Ore));
+}
+
+#[tokio::test]
+async fn stop_as_the_last_anvil_finishes_ends_the_run() {
+    let shop = Workshop::open().await;
+    let script = Script {
+        stop_after: Some("a".into()),
+        ..Script::default()
+    }
+    .on(Phase::Survey, BLUEPRINT)
+    .on(
+        Phase::Found,
+        &[ingot("a", "test -f a.txt"), ingot("b", "test -f b.txt")].join("\n"),
+    )
+    .strike("a", &["CMD: touch a.txt"])
+    .strike("b", &["CMD: touch b.txt"]);
+
+    let result = pipeline::run(Some("Two files"), &script, &config(1)).await;
+    shutdown::reset();
+
+    let err = result.unwrap_err();
+    assert!(matches!(err, SlagError::Interrupted), "{err}");
+    let crucible = shop.crucible();
+    assert_eq!(status(&crucible, "a"), Some(Status::Forged));
+    assert_eq!(status(&crucible, "b"), Some(Status::Ore));
+    assert!(!script.asked().iter().any(|p| p == "forge b gr:1"));
+}
+
+#[tokio::test]
+async fn failed_survey_stops_the_run() {
+    let shop = Workshop::open().await;
+    let script = Script::default();
+
+    let err = pipeline::run(Some("Anything"), &script, &config(1))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SlagError::SurveyFailed(_)), "{err}");
+    assert!(!project_path(&paths().crucible).exists());
+    assert_eq!(script.asked(), ["Survey"]);
+    drop(shop);
+}